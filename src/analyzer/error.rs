@@ -0,0 +1,47 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter, Debug};
+use std::path::PathBuf;
+
+use crate::config_reader::error::ConfigError;
+use crate::parser::error::ParserError;
+use crate::parser::option::ParserOptionError;
+
+#[derive(Debug)]
+pub enum AnalyzerError {
+    ConfigError(ConfigError),
+    ParserError(ParserError),
+    ParserOptionError(ParserOptionError),
+    FileCannotRead(PathBuf),
+    FileCannotWrite(PathBuf),
+    EntryNotFound(PathBuf),
+}
+
+impl Error for AnalyzerError { }
+
+impl Display for AnalyzerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        use AnalyzerError::*;
+        let message = match &self {
+            ConfigError(error) => {
+                format!("{}", error)
+            },
+            ParserError(error) => {
+                format!("{}", error)
+            },
+            ParserOptionError(error) => {
+                format!("{}", error)
+            },
+            FileCannotRead(path) => {
+                format!("Cannot read file {}.", path.to_str().unwrap())
+            },
+            FileCannotWrite(path) => {
+                format!("Cannot write to file {}.", path.to_str().unwrap())
+            },
+            EntryNotFound(path) => {
+                format!("Cannot find entry of {}", path.to_str().unwrap())
+            },
+        };
+        write!(f, "{}", message)
+    }
+}
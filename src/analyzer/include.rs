@@ -0,0 +1,43 @@
+use std::path::{Path, PathBuf};
+
+use onig::Regex;
+
+use crate::analyzer::{DependencyAnalyzer, Reference};
+
+const INCLUDE_PATTERN: &str = r#"^\s*#\s*include\s*"([^"]+)""#;
+
+// `#include "..."` directives of C and C++. `#include <...>` refers to system headers, which are not files of the project
+pub struct IncludeAnalyzer {
+    include_dirs: Vec<PathBuf>,
+}
+
+impl IncludeAnalyzer {
+    pub fn new(include_dirs: Vec<PathBuf>) -> IncludeAnalyzer {
+        IncludeAnalyzer {
+            include_dirs,
+        }
+    }
+}
+
+impl DependencyAnalyzer for IncludeAnalyzer {
+    // the directory of the including file is searched first, then include directories in order
+    fn references(&self, path: &Path, content: &str) -> Vec<Reference> {
+        let regex = Regex::new(INCLUDE_PATTERN).unwrap();
+        let current_dir = path.parent().unwrap().to_path_buf();
+        let mut references = vec![];
+        for line in content.lines() {
+            let name = match regex.captures(line).and_then(|capture| capture.at(1).map(|name| name.to_string())) {
+                Some(name) => name,
+                None => continue,
+            };
+            let file = std::iter::once(&current_dir).chain(self.include_dirs.iter())
+                .map(|dir| dir.join(&name))
+                .find(|file| file.is_file());
+            match file {
+                Some(file) => references.push(Reference::Resolved(file)),
+                None => references.push(Reference::Unresolved(format!("#include \"{}\"", name))),
+            }
+        }
+        references
+    }
+}
@@ -0,0 +1,257 @@
+pub mod option;
+use option::*;
+
+pub mod error;
+use error::*;
+
+mod rust;
+mod include;
+
+use crate::parser::{self, option::ParserOptions};
+use crate::config_reader;
+use crate::wispha::common::*;
+use crate::helper::line_editor::{self, LineEdit};
+use crate::strings::*;
+
+use std::path::{Path, PathBuf, Component};
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+
+type Result<T> = std::result::Result<T, AnalyzerError>;
+
+// a reference in source code to another file
+pub enum Reference {
+    Resolved(PathBuf),
+    Unresolved(String),
+}
+
+// find the files a source file depends on from its content
+pub trait DependencyAnalyzer {
+    fn references(&self, path: &Path, content: &str) -> Vec<Reference>;
+}
+
+// analyzers of files are looked up by extension
+pub struct AnalyzerRegistry {
+    analyzers: HashMap<String, Rc<dyn DependencyAnalyzer>>,
+}
+
+impl AnalyzerRegistry {
+    pub fn default(root_dir: &PathBuf, options: &AnalyzerOptions) -> AnalyzerRegistry {
+        let mut registry = AnalyzerRegistry {
+            analyzers: HashMap::new(),
+        };
+        registry.register("rs", Rc::new(rust::RustAnalyzer::new(root_dir)));
+        let include_dirs = options.include_dirs.iter().map(|include_dir| root_dir.join(include_dir)).collect();
+        let include_analyzer: Rc<dyn DependencyAnalyzer> = Rc::new(include::IncludeAnalyzer::new(include_dirs));
+        for extension in &["c", "cc", "cpp", "cxx", "h", "hh", "hpp", "hxx"] {
+            registry.register(extension, Rc::clone(&include_analyzer));
+        }
+        registry
+    }
+
+    pub fn register(&mut self, extension: &str, analyzer: Rc<dyn DependencyAnalyzer>) {
+        self.analyzers.insert(extension.to_string(), analyzer);
+    }
+
+    fn analyzer_of(&self, path: &Path) -> Option<&Rc<dyn DependencyAnalyzer>> {
+        let extension = path.extension()?.to_str()?;
+        self.analyzers.get(extension)
+    }
+}
+
+// a dependency found in code, with the reference to be written in `.wispha` files
+pub struct InferredDependency {
+    pub path: PathBuf,
+    pub reference: String,
+}
+
+// how the declared dependencies of a source file differ from its code
+pub struct SourceDependencies {
+    pub path: PathBuf,
+    pub file_path: PathBuf,
+    pub line_number: usize,
+    pub missing: Vec<InferredDependency>, // found in code but not declared
+    pub extra: Vec<PathBuf>, // declared but not found in code
+    pub unresolved: Vec<String>, // references in code to no file
+    property_lines: (usize, usize), // where new dependencies are inserted after
+}
+
+// `path` is the path of the root `.wispha` file. Only source files with differences are returned
+pub fn infer(path: &PathBuf, options: AnalyzerOptions) -> Result<Vec<SourceDependencies>> {
+    let root_dir = path.parent().unwrap().to_path_buf();
+    let mut parser_options = ParserOptions::default();
+    let config = config_reader::read_configs_in_dir(&root_dir).or_else(|error| Err(AnalyzerError::ConfigError(error)))?;
+    if let Some(config) = &config {
+        parser_options.update_from_config(config).or_else(|error| Err(AnalyzerError::ParserOptionError(error)))?;
+    }
+    options.update_parser_options(&mut parser_options);
+    let root = parser::parse(path, parser_options).or_else(|error| Err(AnalyzerError::ParserError(error)))?;
+
+    let registry = AnalyzerRegistry::default(&root_dir, &options);
+    let mut entries = HashMap::new();
+    push_into_entries(&root, &mut entries);
+    let mut sources = vec![];
+    infer_in_entry(&root, &entries, &registry, &mut sources)?;
+    Ok(sources)
+}
+
+fn push_into_entries(entry: &Rc<RefCell<WisphaEntry>>, entries: &mut HashMap<PathBuf, Rc<RefCell<WisphaEntry>>>) {
+    let properties = &entry.borrow().properties;
+    if properties.has_own_path() {
+        entries.insert(normalized_path(&properties.absolute_path), Rc::clone(entry));
+    }
+    for sub_entry in &*entry.borrow().sub_entries.borrow() {
+        push_into_entries(sub_entry, entries);
+    }
+}
+
+// `..` components are resolved without touching the file system, so that paths of entries and of references agree
+fn normalized_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => {
+                normalized.pop();
+            },
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn infer_in_entry(entry: &Rc<RefCell<WisphaEntry>>,
+                  entries: &HashMap<PathBuf, Rc<RefCell<WisphaEntry>>>,
+                  registry: &AnalyzerRegistry,
+                  sources: &mut Vec<SourceDependencies>) -> Result<()> {
+    let absolute_path = entry.borrow().properties.absolute_path.clone();
+    let analyzer = registry.analyzer_of(&absolute_path).filter(|_| entry.borrow().properties.has_own_path() && absolute_path.is_file());
+    if let Some(analyzer) = analyzer {
+        let content = fs::read_to_string(&absolute_path).or(Err(AnalyzerError::FileCannotRead(absolute_path.clone())))?;
+        let source = compare_dependencies(entry, entries, registry, analyzer.references(&absolute_path, &content));
+        if !source.missing.is_empty() || !source.extra.is_empty() || !source.unresolved.is_empty() {
+            sources.push(source);
+        }
+    }
+    for sub_entry in &*entry.borrow().sub_entries.borrow() {
+        infer_in_entry(sub_entry, entries, registry, sources)?;
+    }
+    Ok(())
+}
+
+fn compare_dependencies(entry: &Rc<RefCell<WisphaEntry>>,
+                        entries: &HashMap<PathBuf, Rc<RefCell<WisphaEntry>>>,
+                        registry: &AnalyzerRegistry,
+                        references: Vec<Reference>) -> SourceDependencies {
+    let borrowed_entry = entry.borrow();
+    let properties = &borrowed_entry.properties;
+    let mut inferred: Vec<Rc<RefCell<WisphaEntry>>> = vec![];
+    let mut unresolved = vec![];
+    for reference in references {
+        match reference {
+            Reference::Resolved(path) => {
+                // a file not recorded is represented by its nearest recorded directory
+                let target = normalized_path(&path).ancestors().find_map(|ancestor| entries.get(ancestor));
+                if let Some(target) = target {
+                    // directories containing the file, the root directory included, are trivial dependencies
+                    let is_trivial = properties.absolute_path.starts_with(&target.borrow().properties.absolute_path);
+                    if !is_trivial && !inferred.iter().any(|inferred| Rc::ptr_eq(inferred, target)) {
+                        inferred.push(Rc::clone(target));
+                    }
+                }
+            },
+            Reference::Unresolved(reference) => unresolved.push(reference),
+        }
+    }
+
+    // a dependency on a directory covers the files in it
+    let declared: Vec<Rc<RefCell<WisphaEntry>>> = borrowed_entry.dependencies.borrow().iter()
+        .filter_map(|dependency| dependency.upgrade())
+        .filter(|dependency| !dependency.borrow().properties.is_virtual())
+        .collect();
+    let covers = |dependency: &Rc<RefCell<WisphaEntry>>, target: &Rc<RefCell<WisphaEntry>>| {
+        target.borrow().properties.absolute_path.starts_with(&dependency.borrow().properties.absolute_path)
+    };
+    let missing = inferred.iter()
+        .filter(|target| !declared.iter().any(|dependency| covers(dependency, target)))
+        .map(|target| InferredDependency {
+            path: path_of_entry(target),
+            reference: reference_of(target),
+        })
+        .collect();
+    // dependencies on files no analyzer understands cannot be judged from code
+    let extra = declared.iter()
+        .filter(|dependency| contains_sources(dependency, registry))
+        .filter(|dependency| !inferred.iter().any(|target| covers(dependency, target)))
+        .map(|dependency| path_of_entry(dependency))
+        .collect();
+
+    let property_lines = properties.property_lines.get(ABSOLUTE_PATH_HEADER)
+        .or(properties.property_lines.get(NAME_HEADER))
+        .cloned()
+        .unwrap_or((1, 1));
+    SourceDependencies {
+        path: path_of_entry(entry),
+        file_path: properties.file_path.clone(),
+        line_number: property_lines.0,
+        missing,
+        extra,
+        unresolved,
+        property_lines,
+    }
+}
+
+// ids are preferred, so that the dependency survives moves
+fn reference_of(entry: &Rc<RefCell<WisphaEntry>>) -> String {
+    match &entry.borrow().properties.id {
+        Some(id) => format!("{}{}", ID_REFERENCE_PREFIX, id),
+        None => path_of_entry(entry).to_str().unwrap().to_string(),
+    }
+}
+
+fn contains_sources(entry: &Rc<RefCell<WisphaEntry>>, registry: &AnalyzerRegistry) -> bool {
+    let borrowed_entry = entry.borrow();
+    if registry.analyzer_of(&borrowed_entry.properties.absolute_path).is_some() {
+        return true;
+    }
+    let result = borrowed_entry.sub_entries.borrow().iter().any(|sub_entry| contains_sources(sub_entry, registry));
+    result
+}
+
+// add missing dependencies to `.wispha` files. Declared dependencies are never removed. Returns the number added
+pub fn write_dependencies(sources: &Vec<SourceDependencies>) -> Result<usize> {
+    let mut contents: HashMap<PathBuf, String> = HashMap::new();
+    let mut edits: HashMap<PathBuf, Vec<LineEdit>> = HashMap::new();
+    let mut count = 0;
+    for source in sources.iter().filter(|source| !source.missing.is_empty()) {
+        let file_path = &source.file_path;
+        if !contents.contains_key(file_path) {
+            let content = fs::read_to_string(file_path).or(Err(AnalyzerError::FileCannotRead(file_path.clone())))?;
+            contents.insert(file_path.clone(), content);
+        }
+        let (header_line, last_line) = source.property_lines;
+        let begin_mark: String = contents[file_path].lines().nth(header_line - 1).unwrap_or("")
+            .trim_start()
+            .chars().take_while(|c| BEGIN_MARK.starts_with(*c))
+            .collect();
+        if begin_mark.is_empty() {
+            return Err(AnalyzerError::EntryNotFound(source.path.clone()));
+        }
+        let mut lines = vec![];
+        for dependency in &source.missing {
+            lines.push(String::new());
+            lines.push(format!("{} [{}]", begin_mark, DEPENDENCY_HEADER));
+            lines.push(dependency.reference.clone());
+        }
+        count += source.missing.len();
+        edits.entry(file_path.clone()).or_insert_with(Vec::new).push(LineEdit::Insert { after: last_line, lines });
+    }
+
+    for (file_path, edits) in edits {
+        let content = line_editor::apply_line_edits(&contents[&file_path], edits);
+        fs::write(&file_path, content).or(Err(AnalyzerError::FileCannotWrite(file_path.clone())))?;
+    }
+    Ok(count)
+}
@@ -0,0 +1,36 @@
+use crate::config_reader::Config;
+use crate::commandline::Infer;
+use crate::strings::*;
+use crate::parser::option::ParserOptions;
+
+pub struct AnalyzerOptions {
+    pub include_dirs: Vec<String>,
+    pub threads: usize,
+}
+
+impl AnalyzerOptions {
+    pub fn default() -> AnalyzerOptions {
+        AnalyzerOptions {
+            include_dirs: vec![],
+            threads: DEFAULT_THREADS,
+        }
+    }
+
+    pub fn update_from_config(&mut self, config: &Config) {
+        if let Some(infer) = &config.infer {
+            if let Some(include_dirs) = &infer.include_dirs {
+                self.include_dirs = include_dirs.clone();
+            }
+        }
+    }
+
+    pub fn update_from_commandline(&mut self, infer: &Infer) {
+        if let Some(threads) = infer.threads {
+            self.threads = threads;
+        }
+    }
+
+    pub fn update_parser_options(&self, parser_options: &mut ParserOptions) {
+        parser_options.threads = self.threads;
+    }
+}
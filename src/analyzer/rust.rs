@@ -0,0 +1,286 @@
+use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use std::fs;
+
+use ignore::WalkBuilder;
+use syn::{Item, Stmt, UseTree};
+
+use crate::analyzer::{DependencyAnalyzer, Reference};
+
+// `mod`, `use` and `extern crate` items, resolved against the crates in the project
+pub struct RustAnalyzer {
+    root_dir: PathBuf,
+    crates: HashMap<String, PathBuf>, // directories of crates in the project, by the name used in code
+}
+
+// the module a piece of code is in
+#[derive(Clone)]
+struct Module {
+    src_dir: PathBuf,
+    path: Option<Vec<String>>, // `None` if the file is not under `src_dir`, so `crate` cannot be resolved
+    dir: PathBuf, // where files of submodules are
+}
+
+impl Module {
+    // `mod name { ... }` in the module
+    fn submodule(&self, name: &str) -> Module {
+        let mut module = self.clone();
+        if let Some(module_path) = &mut module.path {
+            module_path.push(name.to_string());
+        }
+        module.dir = module.dir.join(name);
+        module
+    }
+}
+
+impl RustAnalyzer {
+    pub fn new(root_dir: &PathBuf) -> RustAnalyzer {
+        let mut crates = HashMap::new();
+        for entry in WalkBuilder::new(root_dir).build().filter_map(|entry| entry.ok()) {
+            if entry.file_name() != "Cargo.toml" {
+                continue;
+            }
+            let manifest = fs::read_to_string(entry.path()).ok()
+                .and_then(|content| content.parse::<toml::Value>().ok());
+            let name = manifest.as_ref()
+                .and_then(|manifest| manifest.get("package"))
+                .and_then(|package| package.get("name"))
+                .and_then(|name| name.as_str());
+            if let Some(name) = name {
+                crates.insert(name.replace('-', "_"), entry.path().parent().unwrap().to_path_buf());
+            }
+        }
+        RustAnalyzer {
+            root_dir: root_dir.clone(),
+            crates,
+        }
+    }
+
+    // the nearest directory with `Cargo.toml`, or the root directory
+    fn src_dir_of(&self, path: &Path) -> PathBuf {
+        let crate_dir = path.ancestors().skip(1)
+            .take_while(|ancestor| ancestor.starts_with(&self.root_dir))
+            .find(|ancestor| ancestor.join("Cargo.toml").is_file())
+            .unwrap_or(&self.root_dir);
+        src_dir_of_crate(crate_dir)
+    }
+
+    fn module_of(&self, path: &Path) -> Module {
+        let src_dir = self.src_dir_of(path);
+        let parent = path.parent().unwrap().to_path_buf();
+        let file_name = path.file_name().and_then(|name| name.to_str()).unwrap_or("");
+        let is_module_root = file_name == "mod.rs" || (parent == src_dir && (file_name == "lib.rs" || file_name == "main.rs"));
+        let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("").to_string();
+        let module_path = path.strip_prefix(&src_dir).ok().map(|relative_path| {
+            let mut module_path: Vec<String> = relative_path.parent().unwrap().components()
+                .map(|component| component.as_os_str().to_str().unwrap().to_string())
+                .collect();
+            if !is_module_root {
+                module_path.push(stem.clone());
+            }
+            module_path
+        });
+        let dir = if is_module_root || module_path.is_none() { parent } else { parent.join(&stem) };
+        Module {
+            src_dir,
+            path: module_path,
+            dir,
+        }
+    }
+
+    // files of submodules declared by `mod` items are children of the file, not its dependencies
+    fn push_references_of_items(&self, items: Vec<&Item>, module: &Module, references: &mut Vec<Reference>) {
+        for item in items {
+            match item {
+                Item::Mod(item) => {
+                    let name = item.ident.to_string();
+                    match &item.content {
+                        Some((_, items)) => self.push_references_of_items(items.iter().collect(), &module.submodule(&name), references),
+                        None => {
+                            if file_of_submodule(&module.dir, &name).is_none() {
+                                references.push(Reference::Unresolved(format!("mod {}", name)));
+                            }
+                        },
+                    }
+                },
+                // crates outside the project are not files of it
+                Item::ExternCrate(item) => {
+                    if let Some(crate_dir) = self.crates.get(&item.ident.to_string()) {
+                        references.push(Reference::Resolved(file_of_module(&src_dir_of_crate(crate_dir), &[])));
+                    }
+                },
+                Item::Use(item) => {
+                    for segments in paths_of_use_tree(&item.tree) {
+                        if let Some(file) = self.resolve_use(&segments, module) {
+                            references.push(Reference::Resolved(file));
+                        }
+                    }
+                },
+                // items in function bodies belong to the module of the function
+                Item::Fn(item) => {
+                    let items = item.block.stmts.iter()
+                        .filter_map(|stmt| match stmt {
+                            Stmt::Item(item) => Some(item),
+                            _ => None,
+                        })
+                        .collect();
+                    self.push_references_of_items(items, module, references);
+                },
+                _ => {},
+            }
+        }
+    }
+
+    fn resolve_use(&self, segments: &Vec<String>, module: &Module) -> Option<PathBuf> {
+        let first = segments.first()?;
+        match first.as_str() {
+            "crate" => {
+                module.path.as_ref()?;
+                Some(file_of_module(&module.src_dir, &segments[1..]))
+            },
+            "self" | "super" => {
+                let mut module_path = module.path.clone()?;
+                let mut index = if first == "self" { 1 } else { 0 };
+                while segments.get(index).map(|segment| segment == "super").unwrap_or(false) {
+                    module_path.pop()?;
+                    index += 1;
+                }
+                module_path.extend_from_slice(&segments[index..]);
+                Some(file_of_module(&module.src_dir, &module_path))
+            },
+            _ => {
+                if let Some(crate_dir) = self.crates.get(first) {
+                    return Some(file_of_module(&src_dir_of_crate(crate_dir), &segments[1..]));
+                }
+                // since 2018 edition, paths may start with a submodule of the current module
+                let module_path = module.path.as_ref()?;
+                file_of_submodule(&module.dir, first)?;
+                let mut module_path = module_path.clone();
+                module_path.extend_from_slice(segments);
+                Some(file_of_module(&module.src_dir, &module_path))
+            },
+        }
+    }
+}
+
+impl DependencyAnalyzer for RustAnalyzer {
+    // files which cannot be parsed have no references
+    fn references(&self, path: &Path, content: &str) -> Vec<Reference> {
+        let file = match syn::parse_file(content) {
+            Ok(file) => file,
+            Err(_) => return vec![],
+        };
+        let mut references = vec![];
+        self.push_references_of_items(file.items.iter().collect(), &self.module_of(path), &mut references);
+        references
+    }
+}
+
+fn src_dir_of_crate(crate_dir: &Path) -> PathBuf {
+    let src_dir = crate_dir.join("src");
+    if src_dir.is_dir() { src_dir } else { crate_dir.to_path_buf() }
+}
+
+fn file_of_submodule(dir: &Path, name: &str) -> Option<PathBuf> {
+    let file = dir.join(format!("{}.rs", name));
+    if file.is_file() {
+        return Some(file);
+    }
+    let file = dir.join(name).join("mod.rs");
+    if file.is_file() { Some(file) } else { None }
+}
+
+// the deepest module on `module_path` which has its own file, since the rest may be items in it
+fn file_of_module(src_dir: &Path, module_path: &[String]) -> PathBuf {
+    for length in (1..=module_path.len()).rev() {
+        let dir = module_path[..length - 1].iter().fold(src_dir.to_path_buf(), |dir, segment| dir.join(segment));
+        if let Some(file) = file_of_submodule(&dir, &module_path[length - 1]) {
+            return file;
+        }
+    }
+    ["lib.rs", "main.rs"].iter()
+        .map(|name| src_dir.join(name))
+        .find(|file| file.is_file())
+        .unwrap_or(src_dir.to_path_buf())
+}
+
+// `a::{b, c::{self, d}}` to `a::b`, `a::c` and `a::c::d`. Globs refer to their modules, and renames to the original paths
+fn paths_of_use_tree(tree: &UseTree) -> Vec<Vec<String>> {
+    let mut paths = vec![];
+    expand_into(&vec![], tree, &mut paths);
+    paths.into_iter().filter(|path| !path.is_empty()).collect()
+}
+
+// `self` in a group refers to the prefix, while a leading `self` is kept as the current module
+fn expand_into(prefix: &Vec<String>, tree: &UseTree, paths: &mut Vec<Vec<String>>) {
+    let mut path = prefix.clone();
+    match tree {
+        UseTree::Path(tree) => {
+            path.push(tree.ident.to_string());
+            expand_into(&path, &tree.tree, paths);
+        },
+        UseTree::Name(tree) => {
+            if tree.ident != "self" || prefix.is_empty() {
+                path.push(tree.ident.to_string());
+            }
+            paths.push(path);
+        },
+        UseTree::Rename(tree) => {
+            if tree.ident != "self" || prefix.is_empty() {
+                path.push(tree.ident.to_string());
+            }
+            paths.push(path);
+        },
+        UseTree::Glob(_) => paths.push(path),
+        UseTree::Group(tree) => {
+            for item in &tree.items {
+                expand_into(&path, item, paths);
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn paths_of(tree: &str) -> Vec<String> {
+        let tree: UseTree = syn::parse_str(tree).unwrap();
+        paths_of_use_tree(&tree).iter().map(|path| path.join("::")).collect()
+    }
+
+    fn references_of(content: &str) -> Vec<String> {
+        let root_dir = env::temp_dir().join("wispha-rust-analyzer-missing");
+        let analyzer = RustAnalyzer::new(&root_dir);
+        analyzer.references(&root_dir.join("src").join("lib.rs"), content).iter()
+            .map(|reference| match reference {
+                Reference::Resolved(path) => path.to_str().unwrap().to_string(),
+                Reference::Unresolved(reference) => reference.clone(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn expands_groups() {
+        assert_eq!(paths_of("a::{b, c::{self, d}}"), vec!["a::b", "a::c", "a::c::d"]);
+        assert_eq!(paths_of("self::a::b"), vec!["self::a::b"]);
+    }
+
+    #[test]
+    fn keeps_modules_of_globs_and_renames() {
+        assert_eq!(paths_of("crate::a::*"), vec!["crate::a"]);
+        assert_eq!(paths_of("crate::a::{b as c, self as d}"), vec!["crate::a::b", "crate::a"]);
+    }
+
+    #[test]
+    fn skips_child_modules() {
+        let references = references_of("mod inline { fn f() {} }\n// mod commented;\nconst S: &str = \"mod quoted;\";\nmod missing;\n");
+        assert_eq!(references, vec!["mod missing"]);
+    }
+
+    #[test]
+    fn skips_files_which_cannot_be_parsed() {
+        assert!(references_of("mod missing; fn (").is_empty());
+    }
+}
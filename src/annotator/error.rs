@@ -0,0 +1,55 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter, Debug};
+use std::path::PathBuf;
+
+use crate::config_reader::error::ConfigError;
+use crate::parser::error::ParserError;
+use crate::parser::option::ParserOptionError;
+
+#[derive(Debug)]
+pub enum AnnotatorError {
+    ConfigError(ConfigError),
+    ParserError(ParserError),
+    ParserOptionError(ParserOptionError),
+    FileCannotRead(PathBuf),
+    FileCannotWrite(PathBuf),
+    EntryNotFound(PathBuf),
+    EntryNotFile(PathBuf),
+    UnsupportedFileType(PathBuf),
+}
+
+impl Error for AnnotatorError { }
+
+impl Display for AnnotatorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        use AnnotatorError::*;
+        let message = match &self {
+            ConfigError(error) => {
+                format!("{}", error)
+            },
+            ParserError(error) => {
+                format!("{}", error)
+            },
+            ParserOptionError(error) => {
+                format!("{}", error)
+            },
+            FileCannotRead(path) => {
+                format!("Cannot read file {}.", path.to_str().unwrap())
+            },
+            FileCannotWrite(path) => {
+                format!("Cannot write to file {}.", path.to_str().unwrap())
+            },
+            EntryNotFound(path) => {
+                format!("Cannot find entry of {}", path.to_str().unwrap())
+            },
+            EntryNotFile(path) => {
+                format!("{} is not a file. Only files can be annotated.", path.to_str().unwrap())
+            },
+            UnsupportedFileType(path) => {
+                format!("Don't know how to write comments in {}.", path.to_str().unwrap())
+            },
+        };
+        write!(f, "{}", message)
+    }
+}
@@ -0,0 +1,268 @@
+pub mod option;
+use option::*;
+
+pub mod error;
+use error::*;
+
+use crate::parser::option::ParserOptions;
+use crate::config_reader::{self, PropertyConfig, AnnotationPreference};
+use crate::parser;
+use crate::wispha::{common::*, core::*};
+use crate::helper::line_editor::{self, LineEdit};
+use crate::strings::*;
+
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+
+type Result<T> = std::result::Result<T, AnnotatorError>;
+
+// `wispha:` lines found in the comments of a source file
+pub struct Annotation {
+    pub description: Option<String>,
+    pub properties: HashMap<String, String>,
+    pub description_lines: Option<(usize, usize)>, // from the `wispha: description` line to its last continuation line
+}
+
+// `None` if `path` has no known comment syntax, cannot be read as UTF-8 text, or has no annotation.
+// Only the leading comment block is read, which starts after the shebang and ends at the first line
+// which is neither blank nor a comment.
+//
+// `// wispha: description ...` starts a description, which continues over the following comment lines
+// until an empty comment line, a non-comment line or another `wispha:` line.
+// `// wispha: <property> = <value>` sets a single line property
+pub fn read_annotation(path: &Path) -> Option<Annotation> {
+    let token = line_comment_of(path)?;
+    let content = fs::read_to_string(path).ok()?;
+    let lines: Vec<&str> = content.lines().collect();
+    let first = if content.starts_with("#!") { 1 } else { 0 };
+    let leading_lines = lines[first.min(lines.len())..].iter()
+        .take_while(|line| line.trim().is_empty() || comment_text(line, token).is_some())
+        .count();
+    let lines = &lines[..first + leading_lines];
+    let mut annotation = Annotation {
+        description: None,
+        properties: HashMap::new(),
+        description_lines: None,
+    };
+    let mut found = false;
+    let mut index = first;
+    while index < lines.len() {
+        let text = match comment_text(lines[index], token) {
+            Some(text) if text.starts_with(ANNOTATION_MARK) => text[ANNOTATION_MARK.len()..].trim().to_string(),
+            _ => {
+                index += 1;
+                continue;
+            }
+        };
+        found = true;
+
+        if let Some(first_line) = description_of_annotation(&text) {
+            let mut description_lines = vec![first_line];
+            let mut last = index;
+            while let Some(text) = lines.get(last + 1).and_then(|line| comment_text(line, token)) {
+                if text.is_empty() || text.starts_with(ANNOTATION_MARK) {
+                    break;
+                }
+                description_lines.push(text);
+                last += 1;
+            }
+            let description = description_lines.join(LINE_SEPARATOR).trim().to_string();
+            annotation.description = if description.is_empty() { None } else { Some(description) };
+            annotation.description_lines = Some((index + 1, last + 1));
+            index = last + 1;
+            continue;
+        }
+
+        if let Some(equal_index) = text.find('=') {
+            let name = text[..equal_index].trim().to_string();
+            let value = text[equal_index + 1..].trim().to_string();
+            if !name.is_empty() {
+                annotation.properties.insert(name, value);
+            }
+        }
+        index += 1;
+    }
+
+    if found { Some(annotation) } else { None }
+}
+
+// the text of a comment line starting with `token`, or `None` if `line` is not such a comment
+fn comment_text(line: &str, token: &str) -> Option<String> {
+    let trimmed = line.trim();
+    if !trimmed.starts_with(token) {
+        return None;
+    }
+    let text = trimmed[token.len()..].trim_start_matches(|c| token.contains(c) || c == '!');
+    Some(text.trim().to_string())
+}
+
+// `description <text>` or `description = <text>`
+fn description_of_annotation(text: &str) -> Option<String> {
+    if !text.starts_with(DESCRIPTION_HEADER) {
+        return None;
+    }
+    let rest = &text[DESCRIPTION_HEADER.len()..];
+    if !rest.is_empty() && !rest.starts_with(|c: char| c.is_whitespace() || c == '=') {
+        return None;
+    }
+    Some(rest.trim_start().trim_start_matches('=').trim().to_string())
+}
+
+// merge `annotation` into `properties`, which holds the values recorded in the `.wispha` file.
+// Only declared properties without `source` are merged. Return whether the two sides conflict
+pub fn merge_annotation(properties: &mut WisphaEntryProperties,
+                        annotation: &Annotation,
+                        property_configs: &Vec<PropertyConfig>,
+                        preference: AnnotationPreference) -> bool {
+    let mut conflicting = false;
+
+    if let Some(description) = &annotation.description {
+        let recorded = properties.description.as_ref().filter(|recorded| !recorded.trim().is_empty());
+        match recorded {
+            Some(recorded) => {
+                if recorded.trim() != description {
+                    conflicting = true;
+                    if preference == AnnotationPreference::Source {
+                        properties.description = Some(description.clone());
+                    }
+                }
+            },
+            None => {
+                properties.description = Some(description.clone());
+            }
+        }
+    }
+
+    for property in property_configs {
+        if property.source.is_some() {
+            continue;
+        }
+        if let Some(value) = annotation.properties.get(&property.name) {
+            match properties.customized.get(&property.name) {
+                Some(recorded) => {
+                    if recorded.trim() != value {
+                        conflicting = true;
+                        if preference == AnnotationPreference::Source {
+                            properties.customized.insert(property.name.clone(), value.clone());
+                        }
+                    }
+                },
+                None => {
+                    properties.customized.insert(property.name.clone(), value.clone());
+                }
+            }
+        }
+    }
+
+    conflicting
+}
+
+// write descriptions of entries at `entry_paths` back into their source files.
+// If `entry_paths` is empty, all files with descriptions and a known comment syntax are annotated.
+// Return the annotated files. `path` is the path of the root `.wispha` file
+pub fn annotate(path: &PathBuf, entry_paths: &Vec<PathBuf>, options: AnnotatorOptions) -> Result<Vec<PathBuf>> {
+    let root_dir = path.parent().unwrap().to_path_buf();
+    let mut parser_options = ParserOptions::default();
+    let config = config_reader::read_configs_in_dir(&root_dir).or_else(|error| Err(AnnotatorError::ConfigError(error)))?;
+    if let Some(config) = config {
+        parser_options.update_from_config(&config).or_else(|error| Err(AnnotatorError::ParserOptionError(error)))?;
+    }
+    options.update_parser_options(&mut parser_options);
+    let root = parser::parse(&path, parser_options).or_else(|error| Err(AnnotatorError::ParserError(error)))?;
+
+    let mut entries = vec![];
+    if entry_paths.is_empty() {
+        get_annotatable_entries_from_root(Rc::clone(&root), &mut entries);
+    } else {
+        for entry_path in entry_paths {
+            let target = entry_path.canonicalize().or(Err(AnnotatorError::EntryNotFound(entry_path.clone())))?;
+            let entry = find_entry_by_path(Rc::clone(&root), &target)
+                .ok_or(AnnotatorError::EntryNotFound(entry_path.clone()))?;
+            let is_file = match &entry.borrow().properties.entry_type {
+                WisphaEntryType::File | WisphaEntryType::Custom(_) => true,
+                _ => false,
+            };
+            if !is_file || !target.is_file() {
+                return Err(AnnotatorError::EntryNotFile(entry_path.clone()));
+            }
+            if line_comment_of(&target).is_none() {
+                return Err(AnnotatorError::UnsupportedFileType(entry_path.clone()));
+            }
+            entries.push(entry);
+        }
+    }
+
+    let mut annotated_files = vec![];
+    for entry in entries {
+        let entry = entry.borrow();
+        let description = match &entry.properties.description {
+            Some(description) if !description.trim().is_empty() => description.trim(),
+            _ => continue,
+        };
+        let file_path = &entry.properties.absolute_path;
+        let content = fs::read_to_string(file_path).or(Err(AnnotatorError::FileCannotRead(file_path.clone())))?;
+        let edit = get_annotation_edit(file_path, &content, description)?;
+        let content = line_editor::apply_line_edits(&content, vec![edit]);
+        fs::write(file_path, content).or(Err(AnnotatorError::FileCannotWrite(file_path.clone())))?;
+        annotated_files.push(file_path.clone());
+    }
+    Ok(annotated_files)
+}
+
+fn get_annotatable_entries_from_root(root: Rc<RefCell<WisphaEntry>>, entries: &mut Vec<Rc<RefCell<WisphaEntry>>>) {
+    let is_annotatable = {
+        let properties = &root.borrow().properties;
+        let is_file = match &properties.entry_type {
+            WisphaEntryType::File | WisphaEntryType::Custom(_) => true,
+            _ => false,
+        };
+        is_file
+            && properties.absolute_path.is_file()
+            && line_comment_of(&properties.absolute_path).is_some()
+            && properties.description.as_ref().map(|description| !description.trim().is_empty()).unwrap_or(false)
+    };
+    if is_annotatable {
+        entries.push(Rc::clone(&root));
+    }
+    for sub_entry in &*root.borrow().sub_entries.borrow() {
+        get_annotatable_entries_from_root(Rc::clone(sub_entry), entries);
+    }
+}
+
+// replace the existing `wispha: description` block, or insert one at the top of the file after the shebang.
+// Empty lines of the description are dropped, since they would end the block
+fn get_annotation_edit(file_path: &PathBuf, content: &String, description: &str) -> Result<LineEdit> {
+    let token = line_comment_of(file_path).ok_or(AnnotatorError::UnsupportedFileType(file_path.clone()))?;
+    let mut description_lines = description.lines().filter(|line| !line.trim().is_empty());
+    let mut lines = vec![format!("{} {} {} {}", token, ANNOTATION_MARK, DESCRIPTION_HEADER, description_lines.next().unwrap_or("").trim())];
+    for line in description_lines {
+        lines.push(format!("{} {}", token, line.trim()));
+    }
+
+    if let Some((first, last)) = read_annotation(file_path).and_then(|annotation| annotation.description_lines) {
+        return Ok(LineEdit::Replace { first, last, lines });
+    }
+
+    let after = if content.starts_with("#!") { 1 } else { 0 };
+    let next_line = content.lines().nth(after);
+    if next_line.map(|line| !line.trim().is_empty()).unwrap_or(false) {
+        lines.push(String::new());
+    }
+    Ok(LineEdit::Insert { after, lines })
+}
+
+// the token of line comments in `path`, determined by its extension
+fn line_comment_of(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    let token = match extension.as_str() {
+        "rs" | "c" | "h" | "cpp" | "cc" | "cxx" | "hpp" | "hh" | "hxx" | "m" | "mm" | "swift" | "go" | "java"
+        | "kt" | "kts" | "scala" | "cs" | "js" | "mjs" | "ts" | "php" | "proto" => "//",
+        "py" | "rb" | "pl" | "pm" | "sh" | "bash" | "zsh" | "toml" | "yaml" | "yml" | "r" | "cmake" => "#",
+        "lua" | "hs" | "sql" => "--",
+        _ => return None,
+    };
+    Some(token)
+}
@@ -0,0 +1,25 @@
+use crate::commandline::Annotate;
+use crate::strings::*;
+use crate::parser::option::ParserOptions;
+
+pub struct AnnotatorOptions {
+    pub threads: usize,
+}
+
+impl AnnotatorOptions {
+    pub fn default() -> AnnotatorOptions {
+        AnnotatorOptions {
+            threads: DEFAULT_THREADS,
+        }
+    }
+
+    pub fn update_from_commandline(&mut self, annotate: &Annotate) {
+        if let Some(threads) = annotate.threads {
+            self.threads = threads;
+        }
+    }
+
+    pub fn update_parser_options(&self, parser_options: &mut ParserOptions) {
+        parser_options.threads = self.threads;
+    }
+}
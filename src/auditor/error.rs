@@ -0,0 +1,59 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter, Debug};
+use std::path::PathBuf;
+
+use crate::config_reader::error::ConfigError;
+use crate::parser::error::ParserError;
+use crate::parser::option::ParserOptionError;
+
+#[derive(Debug)]
+pub enum AuditorError {
+    ConfigError(ConfigError),
+    ParserError(ParserError),
+    ParserOptionError(ParserOptionError),
+    CanNotOpenGitRepository(PathBuf),
+    IgnoreError(ignore::Error),
+    FileCannotRead(PathBuf),
+    FileCannotWrite(PathBuf),
+    EntryNotFound(PathBuf),
+    Unexpected,
+}
+
+impl Error for AuditorError { }
+
+impl Display for AuditorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        use AuditorError::*;
+        let message = match &self {
+            ConfigError(error) => {
+                format!("{}", error)
+            },
+            ParserError(error) => {
+                format!("{}", error)
+            },
+            ParserOptionError(error) => {
+                format!("{}", error)
+            },
+            CanNotOpenGitRepository(path) => {
+                format!("Cannot open git repository at {}", path.to_str().unwrap())
+            },
+            IgnoreError(error) => {
+                format!("{}", error)
+            },
+            FileCannotRead(path) => {
+                format!("Cannot read file {}.", path.to_str().unwrap())
+            },
+            FileCannotWrite(path) => {
+                format!("Cannot write to file {}.", path.to_str().unwrap())
+            },
+            EntryNotFound(path) => {
+                format!("Cannot find entry of {}", path.to_str().unwrap())
+            },
+            Unexpected => {
+                format!("Unexpected error.")
+            },
+        };
+        write!(f, "{}", message)
+    }
+}
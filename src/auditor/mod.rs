@@ -0,0 +1,246 @@
+use git2::{Repository, Blame};
+use serde::Serialize;
+use ignore::gitignore::{GitignoreBuilder, Gitignore};
+
+pub mod option;
+use option::*;
+
+pub mod error;
+use error::*;
+
+use crate::parser::option::ParserOptions;
+use crate::config_reader;
+use crate::parser;
+use crate::wispha::common::*;
+use crate::helper::git::{GitHistory, format_time};
+use crate::helper::fingerprint;
+use crate::helper::line_editor::{self, LineEdit};
+use crate::strings::*;
+
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+
+type Result<T> = std::result::Result<T, AuditorError>;
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+#[derive(Serialize)]
+pub struct StaleEntry {
+    pub name: String,
+    #[serde(rename = "file path")]
+    pub absolute_path: String,
+    #[serde(rename = "description modified")]
+    pub description_modified: String,
+    #[serde(rename = "content modified")]
+    pub content_modified: String,
+    #[serde(rename = "days behind")]
+    pub days_behind: i64,
+    #[serde(rename = "commits behind")]
+    pub commits_behind: usize,
+}
+
+pub struct ChangedEntry {
+    pub absolute_path: PathBuf,
+    pub exists: bool,
+}
+
+// `path` is the path of the root `.wispha` file. `parser_options` should be updated from commandline
+fn parse_from_path(path: &PathBuf, mut parser_options: ParserOptions) -> Result<Rc<RefCell<WisphaEntry>>> {
+    let root_dir = path.parent().unwrap().to_path_buf();
+    let config = config_reader::read_configs_in_dir(&root_dir).or_else(|error| Err(AuditorError::ConfigError(error)))?;
+    if let Some(config) = config {
+        parser_options.update_from_config(&config).or_else(|error| Err(AuditorError::ParserOptionError(error)))?;
+    }
+    parser::parse(&path, parser_options).or_else(|error| Err(AuditorError::ParserError(error)))
+}
+
+// `path` is the path of the root `.wispha` file
+pub fn stale_entries_from_path(path: &PathBuf, options: StaleOptions) -> Result<Vec<StaleEntry>> {
+    let root_dir = path.parent().unwrap().to_path_buf();
+
+    let mut parser_options = ParserOptions::default();
+    options.update_parser_options(&mut parser_options);
+    let root = parse_from_path(path, parser_options)?;
+
+    let repository = Repository::discover(&root_dir).or(Err(AuditorError::CanNotOpenGitRepository(root_dir.clone())))?;
+    let git_history = GitHistory::from_path(&root_dir).or(Err(AuditorError::CanNotOpenGitRepository(root_dir.clone())))?;
+
+    let mut blames = HashMap::new();
+    let mut stale_entries = vec![];
+    get_stale_entries_from_root(Rc::clone(&root), &repository, &git_history, &mut blames, &options, &mut stale_entries);
+    Ok(stale_entries)
+}
+
+fn get_stale_entries_from_root<'repo>(root: Rc<RefCell<WisphaEntry>>,
+                                      repository: &'repo Repository,
+                                      git_history: &GitHistory,
+                                      blames: &mut HashMap<PathBuf, Option<Blame<'repo>>>,
+                                      options: &StaleOptions,
+                                      stale_entries: &mut Vec<StaleEntry>) {
+    let entry = root.borrow();
+    // virtual entries have no content to be stale against
+    let description_time = if entry.properties.is_virtual() {
+        None
+    } else {
+        get_description_time(&entry, repository, git_history, blames)
+    };
+    if let Some((description_time, description_offset)) = description_time {
+        let newer_commits: Vec<_> = git_history.commits_of(&entry.properties.absolute_path).iter()
+            .take_while(|commit| commit.time > description_time)
+            .collect();
+        if let Some(last_commit) = newer_commits.first() {
+            let days_behind = (last_commit.time - description_time) / SECONDS_PER_DAY;
+            if days_behind > options.max_age || newer_commits.len() > options.max_commits {
+                stale_entries.push(StaleEntry {
+                    name: entry.properties.name.clone(),
+                    absolute_path: entry.properties.absolute_path.to_str().unwrap().to_string(),
+                    description_modified: format_time(description_time, description_offset),
+                    content_modified: last_commit.time_string(),
+                    days_behind,
+                    commits_behind: newer_commits.len(),
+                });
+            }
+        }
+    }
+    for sub_entry in &*entry.sub_entries.borrow() {
+        get_stale_entries_from_root(Rc::clone(sub_entry), repository, git_history, blames, options, stale_entries);
+    }
+}
+
+// the time of the latest commit touching the lines of `description`.
+// `None` if there is no description, or some of its lines are not committed yet
+fn get_description_time<'repo>(entry: &WisphaEntry,
+                               repository: &'repo Repository,
+                               git_history: &GitHistory,
+                               blames: &mut HashMap<PathBuf, Option<Blame<'repo>>>) -> Option<(i64, i32)> {
+    let (first_line, last_line) = entry.properties.property_lines.get(DESCRIPTION_HEADER)?.clone();
+    let file_path = &entry.properties.file_path;
+    let blame = blames.entry(file_path.clone())
+        .or_insert_with(|| {
+            let relative_path = file_path.strip_prefix(&git_history.workdir).ok()?;
+            repository.blame_file(relative_path, None).ok()
+        })
+        .as_ref()?;
+
+    let mut latest: Option<(i64, i32)> = None;
+    for line in first_line..=last_line {
+        let hunk = blame.get_line(line)?;
+        if hunk.final_commit_id().is_zero() {
+            return None;
+        }
+        let when = hunk.final_signature().when();
+        if latest.map(|(time, _)| when.seconds() > time).unwrap_or(true) {
+            latest = Some((when.seconds(), when.offset_minutes()));
+        }
+    }
+    latest
+}
+
+// entries whose fingerprint differs from the current content. `path` is the path of the root `.wispha` file
+pub fn changed_entries_from_path(path: &PathBuf, options: FingerprintOptions) -> Result<Vec<ChangedEntry>> {
+    let root_dir = path.parent().unwrap().to_path_buf();
+    let ignored = get_ignored_files_from_root(&root_dir, &options.ignored_files)?;
+
+    let mut parser_options = ParserOptions::default();
+    options.update_parser_options(&mut parser_options);
+    let root = parse_from_path(path, parser_options)?;
+
+    let mut changed_entries = vec![];
+    get_changed_entries_from_root(Rc::clone(&root), &ignored, &options, &mut changed_entries)?;
+    Ok(changed_entries)
+}
+
+// update fingerprints of entries at `entry_paths` to their current content
+pub fn acknowledge(path: &PathBuf, entry_paths: &Vec<PathBuf>, options: FingerprintOptions) -> Result<()> {
+    let root_dir = path.parent().unwrap().to_path_buf();
+    let ignored = get_ignored_files_from_root(&root_dir, &options.ignored_files)?;
+
+    let mut parser_options = ParserOptions::default();
+    options.update_parser_options(&mut parser_options);
+    let root = parse_from_path(path, parser_options)?;
+
+    let mut contents: HashMap<PathBuf, String> = HashMap::new();
+    let mut edits: HashMap<PathBuf, Vec<LineEdit>> = HashMap::new();
+    for entry_path in entry_paths {
+        let target = entry_path.canonicalize().or(Err(AuditorError::EntryNotFound(entry_path.clone())))?;
+        let entry = find_entry_by_path(Rc::clone(&root), &target)
+            .ok_or(AuditorError::EntryNotFound(entry_path.clone()))?;
+        let entry = entry.borrow();
+        let fingerprint = fingerprint::fingerprint_of_path(&target, &ignored, options.allow_hidden_files, &options.wispha_name)
+            .or(Err(AuditorError::FileCannotRead(target.clone())))?;
+
+        let file_path = entry.properties.file_path.clone();
+        if !contents.contains_key(&file_path) {
+            let content = fs::read_to_string(&file_path).or(Err(AuditorError::FileCannotRead(file_path.clone())))?;
+            contents.insert(file_path.clone(), content);
+        }
+        let edit = get_fingerprint_edit(&entry, &contents[&file_path], fingerprint)?;
+        edits.entry(file_path).or_insert_with(Vec::new).push(edit);
+    }
+
+    for (file_path, edits) in edits {
+        let content = line_editor::apply_line_edits(&contents[&file_path], edits);
+        fs::write(&file_path, content).or(Err(AuditorError::FileCannotWrite(file_path.clone())))?;
+    }
+    Ok(())
+}
+
+fn get_ignored_files_from_root(root_dir: &PathBuf, ignored_files: &Vec<String>) -> Result<Gitignore> {
+    let mut ignore_builder = GitignoreBuilder::new(root_dir);
+    for ignored_file in ignored_files {
+        ignore_builder.add_line(None, ignored_file).or_else(|error| Err(AuditorError::IgnoreError(error)))?;
+    }
+    let wispha_ignore = ignore_builder.build().or_else(|error| Err(AuditorError::IgnoreError(error)))?;
+    Ok(wispha_ignore)
+}
+
+fn get_changed_entries_from_root(root: Rc<RefCell<WisphaEntry>>,
+                                 ignored: &Gitignore,
+                                 options: &FingerprintOptions,
+                                 changed_entries: &mut Vec<ChangedEntry>) -> Result<()> {
+    let entry = root.borrow();
+    let recorded_fingerprint = entry.properties.fingerprint.as_ref().filter(|_| !entry.properties.is_virtual());
+    if let Some(recorded_fingerprint) = recorded_fingerprint {
+        let absolute_path = &entry.properties.absolute_path;
+        if !absolute_path.exists() {
+            changed_entries.push(ChangedEntry { absolute_path: absolute_path.clone(), exists: false });
+        } else {
+            let fingerprint = fingerprint::fingerprint_of_path(absolute_path, ignored, options.allow_hidden_files, &options.wispha_name)
+                .or(Err(AuditorError::FileCannotRead(absolute_path.clone())))?;
+            if &fingerprint != recorded_fingerprint {
+                changed_entries.push(ChangedEntry { absolute_path: absolute_path.clone(), exists: true });
+            }
+        }
+    }
+    for sub_entry in &*entry.sub_entries.borrow() {
+        get_changed_entries_from_root(Rc::clone(sub_entry), ignored, options, changed_entries)?;
+    }
+    Ok(())
+}
+
+// replace the body of the `fingerprint` property, or insert one after `file path` if there is none
+fn get_fingerprint_edit(entry: &WisphaEntry, content: &String, fingerprint: String) -> Result<LineEdit> {
+    let property_lines = &entry.properties.property_lines;
+    if let Some((header_line, last_line)) = property_lines.get(FINGERPRINT_HEADER) {
+        return Ok(LineEdit::Replace {
+            first: header_line + 1,
+            last: *last_line.max(header_line),
+            lines: vec![fingerprint],
+        });
+    }
+
+    let (header_line, last_line) = property_lines.get(ABSOLUTE_PATH_HEADER)
+        .or(property_lines.get(NAME_HEADER))
+        .ok_or(AuditorError::EntryNotFound(entry.properties.absolute_path.clone()))?;
+    let begin_mark: String = content.lines().nth(header_line - 1).unwrap_or("")
+        .trim_start()
+        .chars().take_while(|c| BEGIN_MARK.starts_with(*c))
+        .collect();
+    Ok(LineEdit::Insert {
+        after: *last_line,
+        lines: vec![String::new(), format!("{} [{}]", begin_mark, FINGERPRINT_HEADER), fingerprint],
+    })
+}
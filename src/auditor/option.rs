@@ -0,0 +1,96 @@
+use crate::config_reader::Config;
+use crate::commandline::{Stale, Verify, Ack};
+use crate::strings::*;
+use crate::parser::option::ParserOptions;
+
+pub struct StaleOptions {
+    pub max_age: i64, // in days
+    pub max_commits: usize,
+    pub threads: usize,
+}
+
+impl StaleOptions {
+    pub fn default() -> StaleOptions {
+        StaleOptions {
+            max_age: DEFAULT_STALE_MAX_AGE,
+            max_commits: DEFAULT_STALE_MAX_COMMITS,
+            threads: DEFAULT_THREADS,
+        }
+    }
+
+    pub fn update_from_config(&mut self, config: &Config) {
+        if let Some(stale) = &config.stale {
+            if let Some(max_age) = stale.max_age {
+                self.max_age = max_age;
+            }
+            if let Some(max_commits) = stale.max_commits {
+                self.max_commits = max_commits;
+            }
+        }
+    }
+
+    pub fn update_from_commandline(&mut self, stale: &Stale) {
+        if let Some(max_age) = stale.max_age {
+            self.max_age = max_age;
+        }
+        if let Some(max_commits) = stale.max_commits {
+            self.max_commits = max_commits;
+        }
+        if let Some(threads) = stale.threads {
+            self.threads = threads;
+        }
+    }
+
+    pub fn update_parser_options(&self, parser_options: &mut ParserOptions) {
+        parser_options.threads = self.threads;
+    }
+}
+
+pub struct FingerprintOptions {
+    pub ignored_files: Vec<String>,
+    pub allow_hidden_files: bool,
+    pub wispha_name: String,
+    pub threads: usize,
+}
+
+impl FingerprintOptions {
+    pub fn default() -> FingerprintOptions {
+        FingerprintOptions {
+            ignored_files: vec![],
+            allow_hidden_files: false,
+            wispha_name: DEFAULT_FILE_NAME_STR.to_string(),
+            threads: DEFAULT_THREADS,
+        }
+    }
+
+    // fingerprints of directories must be computed with the same rules as `generate`
+    pub fn update_from_config(&mut self, config: &Config) {
+        if let Some(generate) = &config.generate {
+            if let Some(ignored_files) = &generate.ignored_files {
+                self.ignored_files = ignored_files.clone();
+            }
+            if let Some(allow_hidden_files) = &generate.allow_hidden_files {
+                self.allow_hidden_files = *allow_hidden_files;
+            }
+            if let Some(wispha_name) = &generate.wispha_name {
+                self.wispha_name = wispha_name.clone();
+            }
+        }
+    }
+
+    pub fn update_from_verify(&mut self, verify: &Verify) {
+        if let Some(threads) = verify.threads {
+            self.threads = threads;
+        }
+    }
+
+    pub fn update_from_ack(&mut self, ack: &Ack) {
+        if let Some(threads) = ack.threads {
+            self.threads = threads;
+        }
+    }
+
+    pub fn update_parser_options(&self, parser_options: &mut ParserOptions) {
+        parser_options.threads = self.threads;
+    }
+}
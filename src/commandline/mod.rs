@@ -8,6 +8,7 @@ use std::result::Result;
 use crate::manipulator::{Manipulator, error::ManipulatorError};
 use crate::commandline::input_parser::InputParser;
 use crate::parser::option::ParserOptions;
+use crate::wispha::common::TagFilter;
 
 mod input_parser;
 
@@ -24,16 +25,44 @@ pub enum Subcommand {
     Look(Look),
     State(State),
     Convert(Convert),
+    Stale(Stale),
+    Verify(Verify),
+    Ack(Ack),
+    Annotate(Annotate),
+    Restructure(Restructure),
+    Lint(Lint),
+    Query(Query),
+    Impact(Impact),
+    Infer(Infer),
 }
 
 #[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
 pub struct Generate {
     #[structopt(short, long)]
     pub flat: bool,
     #[structopt(short, long)]
     pub recursively: bool,
+    #[structopt(long)]
+    pub hybrid: bool,
+    #[structopt(long)]
+    pub max_depth: Option<usize>,
+    #[structopt(long)]
+    pub hybrid_levels: Option<usize>,
+    #[structopt(long)]
+    pub hybrid_min_files: Option<usize>,
     #[structopt(short, long)]
     pub all: bool,
+    #[structopt(long)]
+    pub fingerprint: bool,
+    #[structopt(long)]
+    pub extract_descriptions: bool,
+    #[structopt(long)]
+    pub assign_ids: bool,
+    #[structopt(long)]
+    pub program_entries: bool,
+    #[structopt(long)]
+    pub ctags: Option<PathBuf>,
     #[structopt(short, long)]
     pub threads: Option<usize>,
     pub path: Option<PathBuf>,
@@ -63,9 +92,119 @@ pub struct Convert {
     pub output: Option<PathBuf>,
     #[structopt(short, long)]
     pub language: Option<String>,
+    #[structopt(long, raw(number_of_values = "1"))]
+    pub tag: Vec<String>,
+    pub path: PathBuf,
+}
+
+#[derive(StructOpt)]
+pub struct Query {
+    #[structopt(short, long)]
+    pub threads: Option<usize>,
+    #[structopt(long, raw(number_of_values = "1"))]
+    pub tag: Vec<String>,
+    pub path: PathBuf,
+}
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct Stale {
+    #[structopt(short, long)]
+    pub threads: Option<usize>,
+    #[structopt(long)]
+    pub max_age: Option<i64>,
+    #[structopt(long)]
+    pub max_commits: Option<usize>,
+    #[structopt(short, long)]
+    pub output: Option<PathBuf>,
+    pub path: PathBuf,
+}
+
+#[derive(StructOpt)]
+pub struct Verify {
+    #[structopt(short, long)]
+    pub threads: Option<usize>,
     pub path: PathBuf,
 }
 
+#[derive(StructOpt)]
+pub struct Ack {
+    #[structopt(short, long)]
+    pub threads: Option<usize>,
+    pub path: PathBuf,
+    pub entries: Vec<PathBuf>,
+}
+
+#[derive(StructOpt)]
+pub struct Annotate {
+    #[structopt(short, long)]
+    pub threads: Option<usize>,
+    pub path: PathBuf,
+    pub entries: Vec<PathBuf>,
+}
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct Restructure {
+    #[structopt(short, long)]
+    pub flat: bool,
+    #[structopt(short, long)]
+    pub recursively: bool,
+    #[structopt(long)]
+    pub hybrid: bool,
+    #[structopt(long)]
+    pub hybrid_levels: Option<usize>,
+    #[structopt(long)]
+    pub hybrid_min_files: Option<usize>,
+    #[structopt(short, long)]
+    pub threads: Option<usize>,
+    pub path: PathBuf,
+}
+
+#[derive(StructOpt)]
+pub struct Lint {
+    #[structopt(short, long)]
+    pub threads: Option<usize>,
+    pub path: PathBuf,
+}
+
+#[derive(StructOpt)]
+pub struct Impact {
+    #[structopt(short, long)]
+    pub threads: Option<usize>,
+    #[structopt(short, long)]
+    pub depth: Option<usize>,
+    pub path: PathBuf,
+    pub entries: Vec<PathBuf>,
+}
+
+#[derive(StructOpt)]
+pub struct Infer {
+    #[structopt(short, long)]
+    pub threads: Option<usize>,
+    #[structopt(long, conflicts_with = "write")]
+    pub check: bool,
+    #[structopt(long)]
+    pub write: bool,
+    pub path: PathBuf,
+}
+
+impl Impact {
+    pub fn update_parser_options(&self, options: &mut ParserOptions) {
+        if let Some(threads) = &self.threads {
+            options.threads = threads.clone();
+        }
+    }
+}
+
+impl Query {
+    pub fn update_parser_options(&self, options: &mut ParserOptions) {
+        if let Some(threads) = &self.threads {
+            options.threads = threads.clone();
+        }
+    }
+}
+
 impl Convert {
     pub fn update_parser_options(&self, options: &mut ParserOptions) {
         if let Some(threads) = &self.threads {
@@ -86,6 +225,8 @@ pub enum LookSubcommand {
     Cd(Cd),
     Ls(Ls),
     Info(Info),
+    Rdeps(Rdeps),
+    Show(Show),
     Quit,
 }
 
@@ -100,6 +241,20 @@ pub struct Cd {
 pub struct Ls {
     #[structopt(short, long)]
     pub local: bool,
+    #[structopt(long, raw(number_of_values = "1"))]
+    pub tag: Vec<String>,
+    pub path: Option<PathBuf>,
+}
+
+#[derive(StructOpt)]
+pub struct Rdeps {
+    #[structopt(short, long)]
+    pub depth: Option<usize>,
+    pub path: Option<PathBuf>,
+}
+
+#[derive(StructOpt)]
+pub struct Show {
     pub path: Option<PathBuf>,
 }
 
@@ -166,15 +321,16 @@ fn continue_program_with_error(manipulator: &mut Manipulator, bstdin: &mut BufRe
         },
 
         LookSubcommand::Ls(ls) => {
+            let filters: Vec<TagFilter> = ls.tag.iter().map(|tag| TagFilter::from(tag)).collect();
             match ls.path {
                 Some(path) => {
                     if ls.local {
-                        let list = manipulator.list_of_local_path(&path)?;
+                        let list = manipulator.list_of_local_path(&path, &filters)?;
                         if list.len() > 0 {
                             println!("{}", list);
                         }
                     } else {
-                        let list = manipulator.list_of_path(&path)?;
+                        let list = manipulator.list_of_path(&path, &filters)?;
                         if list.len() > 0 {
                             println!("{}", list);
                         }
@@ -182,7 +338,7 @@ fn continue_program_with_error(manipulator: &mut Manipulator, bstdin: &mut BufRe
                 }
 
                 None => {
-                    let list = manipulator.current_list();
+                    let list = manipulator.current_list(&filters);
 
                     if list.len() > 0 {
                         println!("{}", list);
@@ -195,6 +351,21 @@ fn continue_program_with_error(manipulator: &mut Manipulator, bstdin: &mut BufRe
             println!("{}", manipulator.info_of_property(&info.name)?);
         }
 
+        // only direct dependents unless depth is given
+        LookSubcommand::Rdeps(rdeps) => {
+            let list = manipulator.dependents_of_path(&rdeps.path, Some(rdeps.depth.unwrap_or(1)))?;
+            if list.len() > 0 {
+                println!("{}", list);
+            }
+        },
+
+        LookSubcommand::Show(show) => {
+            let lines = manipulator.lines_of_path(&show.path)?;
+            if lines.len() > 0 {
+                println!("{}", lines);
+            }
+        },
+
         LookSubcommand::Quit => {
             return Ok(ProgramState::Quiting);
         },
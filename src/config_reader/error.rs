@@ -7,6 +7,10 @@ use toml;
 #[derive(Debug)]
 pub enum ConfigError {
     DeserializeError(toml::de::Error),
+    UnknownPropertyType(String, String),
+    EnumWithoutValues(String),
+    InvalidPattern(String, String),
+    UnknownColor(String, String),
 }
 
 impl Error for ConfigError { }
@@ -19,6 +23,18 @@ impl Display for ConfigError {
                 let error_message = format!("{}", toml_error);
                 write!(f, "{}", error_message)
             },
+            UnknownPropertyType(name, property_type) => {
+                write!(f, "Unknown type {} of property {}.", property_type, name)
+            },
+            EnumWithoutValues(name) => {
+                write!(f, "Property {} of enum type must have values.", name)
+            },
+            InvalidPattern(name, pattern) => {
+                write!(f, "Pattern {} of property {} is not a valid regular expression.", pattern, name)
+            },
+            UnknownColor(name, color) => {
+                write!(f, "Unknown color {} of entry type {}.", color, name)
+            },
         }
     }
 }
\ No newline at end of file
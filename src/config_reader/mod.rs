@@ -1,20 +1,33 @@
 use std::path::PathBuf;
 use std::fs;
+use std::collections::HashMap;
 
 use toml;
 use serde::Deserialize;
+use console::Color;
 
 pub mod error;
 use error::ConfigError;
 
+pub mod schema;
+use schema::PropertySchema;
+
 type Result<T> = std::result::Result<T, ConfigError>;
 
 use crate::strings::*;
+use crate::wispha::core::WisphaEntryType;
 
 #[derive(Deserialize)]
 pub struct Config {
     pub generate: Option<GenerateConfig>,
     pub properties: Option<Vec<PropertyConfig>>,
+    pub stale: Option<StaleConfig>,
+    pub annotation: Option<AnnotationConfig>,
+    pub rules: Option<Vec<RuleConfig>>,
+    pub entry_types: Option<Vec<EntryTypeConfig>>,
+    pub dependency_rules: Option<Vec<DependencyRuleConfig>>,
+    pub infer: Option<InferConfig>,
+    pub externals: Option<HashMap<String, String>>, // alias of project and the path of its root `.wispha` file, relative to the root directory
 }
 
 #[derive(Deserialize, Clone)]
@@ -22,6 +35,75 @@ pub struct GenerateConfig {
     pub allow_hidden_files: Option<bool>,
     pub ignored_files: Option<Vec<String>>,
     pub wispha_name: Option<String>,
+    pub fingerprint: Option<bool>,
+    pub extract_descriptions: Option<bool>,
+    pub assign_ids: Option<bool>,
+    pub program_entries: Option<bool>,
+    pub layer: Option<String>,
+    pub max_depth: Option<usize>,
+    pub hybrid_levels: Option<usize>,
+    pub hybrid_min_files: Option<usize>,
+    pub overrides: Option<Vec<LayoutOverrideConfig>>,
+}
+
+// layout of the subtree of a directory, `path` is relative to the root directory
+#[derive(Deserialize, Clone)]
+pub struct LayoutOverrideConfig {
+    pub path: String,
+    pub layer: Option<String>,
+    pub max_depth: Option<usize>,
+    pub hybrid_levels: Option<usize>,
+    pub hybrid_min_files: Option<usize>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct StaleConfig {
+    pub max_age: Option<i64>,
+    pub max_commits: Option<usize>,
+}
+
+// default properties of generated entries matching a gitignore-style pattern
+#[derive(Deserialize, Clone)]
+pub struct RuleConfig {
+    #[serde(rename = "match")]
+    pub pattern: String,
+    pub properties: Option<HashMap<String, String>>,
+    pub entry_type: Option<String>,
+}
+
+// an entry type besides `directory`, `file` and `program entry`, or settings of a built-in type
+#[derive(Deserialize, Clone)]
+pub struct EntryTypeConfig {
+    pub name: String,
+    pub required_properties: Option<Vec<String>>,
+    pub color: Option<String>, // color of names of entries of this type in `ls`
+}
+
+// entries matching `from` may not depend on entries matching any of `forbid`.
+// Patterns are gitignore-style and relative to the root directory
+#[derive(Deserialize, Clone)]
+pub struct DependencyRuleConfig {
+    pub from: String,
+    pub forbid: Vec<String>,
+}
+
+// directories searched for `#include "..."`, relative to the root directory
+#[derive(Deserialize, Clone)]
+pub struct InferConfig {
+    pub include_dirs: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct AnnotationConfig {
+    pub prefer: Option<AnnotationPreference>,
+}
+
+// which side wins when an annotation in a source file conflicts with the `.wispha` file
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AnnotationPreference {
+    Source,
+    Wispha,
 }
 
 #[derive(Deserialize, Clone)]
@@ -29,6 +111,13 @@ pub struct PropertyConfig {
     pub name: String,
     pub default_value: Option<String>,
     pub allow_multi_line: Option<bool>,
+    pub source: Option<String>,
+    pub inherit: Option<bool>, // entries without the property take the value of the nearest ancestor
+    #[serde(rename = "type")]
+    pub property_type: Option<String>,
+    pub values: Option<Vec<String>>, // allowed values of `enum` type
+    pub required: Option<bool>,
+    pub pattern: Option<String>,
 }
 
 pub fn read_configs_in_dir(dir: &PathBuf) -> Result<Option<Config>> {
@@ -49,5 +138,63 @@ pub fn read_configs_from_path(path: &PathBuf) -> Result<Option<Config>> {
 }
 
 pub fn read_configs(content: String) -> Result<Config> {
-    toml::from_str::<Config>(&content).or_else(|error| Err(ConfigError::DeserializeError(error)))
-}
\ No newline at end of file
+    let config = toml::from_str::<Config>(&content).or_else(|error| Err(ConfigError::DeserializeError(error)))?;
+    if let Some(properties) = &config.properties {
+        for property in properties {
+            PropertySchema::from_config(property)?;
+        }
+    }
+    if let Some(entry_types) = &config.entry_types {
+        for entry_type in entry_types {
+            if let Some(color) = &entry_type.color {
+                color_of(color).ok_or(ConfigError::UnknownColor(entry_type.name.clone(), color.clone()))?;
+            }
+        }
+    }
+    Ok(config)
+}
+
+// names of properties with `inherit = true`
+pub fn inherited_property_names(properties: &Vec<PropertyConfig>) -> Vec<String> {
+    properties.iter()
+        .filter(|property| property.inherit == Some(true))
+        .map(|property| property.name.clone())
+        .collect()
+}
+
+// schemas of declared properties. Schemas are validated when reading configs
+pub fn property_schemas(properties: &Vec<PropertyConfig>) -> HashMap<String, PropertySchema> {
+    properties.iter()
+        .filter_map(|property| PropertySchema::from_config(property).ok().map(|schema| (property.name.clone(), schema)))
+        .collect()
+}
+
+// names of properties of list type
+pub fn list_property_names(properties: &Vec<PropertyConfig>) -> Vec<String> {
+    property_schemas(properties).into_iter()
+        .filter(|(_, schema)| schema.is_list())
+        .map(|(name, _)| name)
+        .collect()
+}
+
+// names of declared entry types which are not built-in
+pub fn custom_entry_type_names(entry_types: &Vec<EntryTypeConfig>) -> Vec<String> {
+    entry_types.iter()
+        .filter(|entry_type| WisphaEntryType::from(entry_type.name.clone()).is_none())
+        .map(|entry_type| entry_type.name.clone())
+        .collect()
+}
+
+pub fn color_of(color: &str) -> Option<Color> {
+    match color {
+        "black" => Some(Color::Black),
+        "red" => Some(Color::Red),
+        "green" => Some(Color::Green),
+        "yellow" => Some(Color::Yellow),
+        "blue" => Some(Color::Blue),
+        "magenta" => Some(Color::Magenta),
+        "cyan" => Some(Color::Cyan),
+        "white" => Some(Color::White),
+        _ => None,
+    }
+}
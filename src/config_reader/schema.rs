@@ -0,0 +1,184 @@
+use onig::Regex;
+use serde_json::Value;
+
+use std::sync::Arc;
+
+use crate::config_reader::{PropertyConfig, error::ConfigError};
+use crate::wispha::common::list_items;
+use crate::strings::*;
+
+type Result<T> = std::result::Result<T, ConfigError>;
+
+const DATE_PATTERN: &str = r"\d{4}-(0[1-9]|1[0-2])-(0[1-9]|[12]\d|3[01])([ T]\d{2}:\d{2}(:\d{2})?( ?(Z|[+-]\d{2}:?\d{2}))?)?";
+const URL_PATTERN: &str = r"[a-zA-Z][a-zA-Z0-9+.\-]*://\S+";
+
+// type of a customized property, declared by `type` in `.wispharc`
+#[derive(Clone)]
+pub enum PropertyType {
+    String,
+    Integer,
+    Bool,
+    Date(Arc<Regex>), // `YYYY-MM-DD`, optionally followed by time and offset. Compiled once
+    Url(Arc<Regex>), // compiled once
+    Enum(Vec<String>), // allowed values
+    List, // one item per line
+}
+
+#[derive(Clone)]
+pub struct PropertySchema {
+    pub property_type: PropertyType,
+    pub required: bool,
+    pub pattern: Option<(String, Arc<Regex>)>, // the whole value, or each item of a list, must match. Compiled once
+}
+
+impl PropertySchema {
+    pub fn from_config(property: &PropertyConfig) -> Result<PropertySchema> {
+        let type_str = property.property_type.clone().unwrap_or(STRING_PROPERTY_TYPE.to_string());
+        let property_type = match type_str.as_str() {
+            STRING_PROPERTY_TYPE => PropertyType::String,
+            INTEGER_PROPERTY_TYPE => PropertyType::Integer,
+            BOOL_PROPERTY_TYPE => PropertyType::Bool,
+            DATE_PROPERTY_TYPE => PropertyType::Date(Arc::new(Regex::new(DATE_PATTERN).unwrap())),
+            URL_PROPERTY_TYPE => PropertyType::Url(Arc::new(Regex::new(URL_PATTERN).unwrap())),
+            LIST_PROPERTY_TYPE => PropertyType::List,
+            ENUM_PROPERTY_TYPE => {
+                match &property.values {
+                    Some(values) if !values.is_empty() => PropertyType::Enum(values.clone()),
+                    _ => return Err(ConfigError::EnumWithoutValues(property.name.clone())),
+                }
+            },
+            _ => return Err(ConfigError::UnknownPropertyType(property.name.clone(), type_str)),
+        };
+        let pattern = match &property.pattern {
+            Some(pattern) => {
+                let regex = Regex::new(pattern).or(Err(ConfigError::InvalidPattern(property.name.clone(), pattern.clone())))?;
+                Some((pattern.clone(), Arc::new(regex)))
+            },
+            None => None,
+        };
+        Ok(PropertySchema {
+            property_type,
+            required: property.required == Some(true),
+            pattern,
+        })
+    }
+
+    pub fn is_list(&self) -> bool {
+        match &self.property_type {
+            PropertyType::List => true,
+            _ => false,
+        }
+    }
+
+    // `Err` with the reason if `value` does not conform to the schema
+    pub fn validate(&self, value: &str) -> std::result::Result<(), String> {
+        let value = value.trim();
+        match &self.property_type {
+            PropertyType::String => {},
+            PropertyType::Integer => {
+                value.parse::<i64>().or(Err(format!("{} is not an integer", value)))?;
+            },
+            PropertyType::Bool => {
+                value.parse::<bool>().or(Err(format!("{} is not true or false", value)))?;
+            },
+            PropertyType::Date(date_regex) => {
+                if !date_regex.is_match(value) {
+                    return Err(format!("{} is not a date like 2020-01-31", value));
+                }
+            },
+            PropertyType::Url(url_regex) => {
+                if !url_regex.is_match(value) {
+                    return Err(format!("{} is not a URL", value));
+                }
+            },
+            PropertyType::Enum(values) => {
+                if !values.iter().any(|allowed| allowed == value) {
+                    return Err(format!("{} is not one of {}", value, values.join(", ")));
+                }
+            },
+            PropertyType::List => {},
+        }
+        if let Some((pattern, regex)) = &self.pattern {
+            for item in self.items_of(value) {
+                if !regex.is_match(&item) {
+                    return Err(format!("{} does not match {}", item, pattern));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // the value in its own type, which is used when exporting
+    pub fn typed_value(&self, value: &str) -> Value {
+        let trimmed = value.trim();
+        match &self.property_type {
+            PropertyType::Integer => {
+                trimmed.parse::<i64>().map(Value::from).unwrap_or(Value::from(value))
+            },
+            PropertyType::Bool => {
+                trimmed.parse::<bool>().map(Value::from).unwrap_or(Value::from(value))
+            },
+            PropertyType::List => {
+                Value::from(self.items_of(value))
+            },
+            _ => Value::from(value),
+        }
+    }
+
+    fn items_of(&self, value: &str) -> Vec<String> {
+        match &self.property_type {
+            PropertyType::List => list_items(value),
+            _ => vec![value.trim().to_string()],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema_of(property_type: &str, values: Option<Vec<&str>>, pattern: Option<&str>) -> PropertySchema {
+        let property = PropertyConfig {
+            name: "property".to_string(),
+            default_value: None,
+            allow_multi_line: None,
+            source: None,
+            inherit: None,
+            property_type: Some(property_type.to_string()),
+            values: values.map(|values| values.iter().map(|value| value.to_string()).collect()),
+            required: None,
+            pattern: pattern.map(|pattern| pattern.to_string()),
+        };
+        PropertySchema::from_config(&property).ok().unwrap()
+    }
+
+    #[test]
+    fn validates_types() {
+        assert!(schema_of("integer", None, None).validate(" 42 ").is_ok());
+        assert!(schema_of("integer", None, None).validate("4.2").is_err());
+        assert!(schema_of("bool", None, None).validate("true").is_ok());
+        assert!(schema_of("bool", None, None).validate("yes").is_err());
+        assert!(schema_of("enum", Some(vec!["core", "net"]), None).validate("net").is_ok());
+        assert!(schema_of("enum", Some(vec!["core", "net"]), None).validate("ui").is_err());
+    }
+
+    #[test]
+    fn validates_dates_and_urls() {
+        let date = schema_of("date", None, None);
+        assert!(date.validate("2020-01-31").is_ok());
+        assert!(date.validate("2020-01-31 12:30:00 +0800").is_ok());
+        assert!(date.validate("2020-13-01").is_err());
+        assert!(date.validate("2020-01-31x").is_err());
+        let url = schema_of("url", None, None);
+        assert!(url.validate("https://example.com/a").is_ok());
+        assert!(url.validate("example.com").is_err());
+    }
+
+    #[test]
+    fn validates_patterns_of_items() {
+        let list = schema_of("list", None, Some("[a-z]+"));
+        assert!(list.validate("api\nnet\n").is_ok());
+        assert!(list.validate("api\nNet").is_err());
+        assert!(schema_of("string", None, Some("[a-z]+")).validate("ab1").is_err());
+    }
+}
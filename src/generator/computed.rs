@@ -0,0 +1,36 @@
+use std::path::Path;
+
+use crate::generator::option::PropertySource;
+use crate::generator::metadata::Metadata;
+use crate::helper::git::GitHistory;
+
+// value of a computed property of the entry at `path`. `None` if the value cannot be determined
+pub fn compute(source: &PropertySource, path: &Path, git_history: &Option<GitHistory>, metadata: &Option<Metadata>) -> Option<String> {
+    use PropertySource::*;
+    match source {
+        GitLastAuthor => {
+            git_history.as_ref()?.last_commit_of(path).map(|commit| commit.author.clone())
+        },
+        GitLastModified => {
+            git_history.as_ref()?.last_commit_of(path).map(|commit| commit.time_string())
+        },
+        GitCommits => {
+            Some(git_history.as_ref()?.commits_of(path).len().to_string())
+        },
+        MetadataSize => {
+            Some(metadata.as_ref()?.size.to_string())
+        },
+        MetadataLines => {
+            metadata.as_ref()?.lines.map(|lines| lines.to_string())
+        },
+        MetadataFiles => {
+            metadata.as_ref()?.files.map(|files| files.to_string())
+        },
+        MetadataLanguage => {
+            metadata.as_ref()?.language.clone()
+        },
+        MetadataExecutable => {
+            metadata.as_ref()?.executable.map(|executable| executable.to_string())
+        },
+    }
+}
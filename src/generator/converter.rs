@@ -7,6 +7,15 @@ use crate::strings::*;
 
 type Result<T> = std::result::Result<T, GeneratorError>;
 
+// paths in `root_dir` start with `$ROOT_DIR`, others are kept absolute
+fn path_string(path: &PathBuf, root_dir: &PathBuf) -> Result<String> {
+    let path = match path.strip_prefix(root_dir) {
+        Ok(relative_path) => PathBuf::from(ROOT_DIR).join(relative_path),
+        Err(_) => path.clone(),
+    };
+    Ok(path.to_str().ok_or(GeneratorError::NameNotValid(path.clone()))?.to_string())
+}
+
 impl WisphaEntryProperties {
     fn to_string(&self, depth: u32, root_dir: &PathBuf) -> Result<String> {
         let mut begin_mark = String::new();
@@ -18,20 +27,17 @@ impl WisphaEntryProperties {
 
         let mut headers = vec![];
 
-        let absolute_path_header_string = format!("{} [{}]", begin_mark, ABSOLUTE_PATH_HEADER);
-        let relative_path = self.absolute_path.clone()
-            .strip_prefix(root_dir).or(Err(GeneratorError::Unexpected))?
-            .to_path_buf();
-        let dir_absolute_path_str = PathBuf::from(ROOT_DIR)
-            .join(relative_path)
-            .to_str().ok_or(GeneratorError::Unexpected)?
-            .to_string();
-        let absolute_path_string = format!("{}{}{}{}",
-                                           absolute_path_header_string,
-                                           LINE_SEPARATOR,
-                                           dir_absolute_path_str,
-                                           LINE_SEPARATOR);
-        headers.push(absolute_path_string);
+        // virtual entries are not backed by the file system
+        if !self.is_virtual() {
+            let absolute_path_header_string = format!("{} [{}]", begin_mark, ABSOLUTE_PATH_HEADER);
+            let dir_absolute_path_str = path_string(&self.absolute_path, root_dir)?;
+            let absolute_path_string = format!("{}{}{}{}",
+                                               absolute_path_header_string,
+                                               LINE_SEPARATOR,
+                                               dir_absolute_path_str,
+                                               LINE_SEPARATOR);
+            headers.push(absolute_path_string);
+        }
 
         let name_header_string = format!("{} [{}]", begin_mark, NAME_HEADER);
         let name_string = format!("{}{}{}{}",
@@ -41,6 +47,16 @@ impl WisphaEntryProperties {
                                   LINE_SEPARATOR);
         headers.push(name_string);
 
+        if let Some(id) = &self.id {
+            let id_header_string = format!("{} [{}]", begin_mark, ID_HEADER);
+            let id_string = format!("{}{}{}{}",
+                                    id_header_string,
+                                    LINE_SEPARATOR,
+                                    id,
+                                    LINE_SEPARATOR);
+            headers.push(id_string);
+        }
+
         let entry_type_header_string = format!("{} [{}]", begin_mark, ENTRY_TYPE_HEADER);
         let entry_type_string = format!("{}{}{}{}",
                                         entry_type_header_string,
@@ -49,6 +65,36 @@ impl WisphaEntryProperties {
                                         LINE_SEPARATOR);
         headers.push(entry_type_string);
 
+        if let Some(kind) = &self.kind {
+            let kind_header_string = format!("{} [{}]", begin_mark, KIND_HEADER);
+            let kind_string = format!("{}{}{}{}",
+                                      kind_header_string,
+                                      LINE_SEPARATOR,
+                                      kind,
+                                      LINE_SEPARATOR);
+            headers.push(kind_string);
+        }
+
+        if let Some(line_range) = &self.line_range {
+            let line_range_header_string = format!("{} [{}]", begin_mark, LINE_RANGE_HEADER);
+            let line_range_string = format!("{}{}{}{}",
+                                            line_range_header_string,
+                                            LINE_SEPARATOR,
+                                            line_range_string(line_range),
+                                            LINE_SEPARATOR);
+            headers.push(line_range_string);
+        }
+
+        if let Some(symbol) = &self.symbol {
+            let symbol_header_string = format!("{} [{}]", begin_mark, SYMBOL_HEADER);
+            let symbol_string = format!("{}{}{}{}",
+                                        symbol_header_string,
+                                        LINE_SEPARATOR,
+                                        symbol,
+                                        LINE_SEPARATOR);
+            headers.push(symbol_string);
+        }
+
         if let Some(description) = &self.description {
             let description_header_string = format!("{} [{}]", begin_mark, DESCRIPTION_HEADER);
             let description_string = format!("{}{}{}{}",
@@ -59,6 +105,26 @@ impl WisphaEntryProperties {
             headers.push(description_string);
         }
 
+        if let Some(fingerprint) = &self.fingerprint {
+            let fingerprint_header_string = format!("{} [{}]", begin_mark, FINGERPRINT_HEADER);
+            let fingerprint_string = format!("{}{}{}{}",
+                                             fingerprint_header_string,
+                                             LINE_SEPARATOR,
+                                             fingerprint,
+                                             LINE_SEPARATOR);
+            headers.push(fingerprint_string);
+        }
+
+        let member_header_string = format!("{} [{}]", begin_mark, MEMBER_HEADER);
+        for member_path_buf in &self.member_path_bufs {
+            let member_string = format!("{}{}{}{}",
+                                        member_header_string,
+                                        LINE_SEPARATOR,
+                                        path_string(member_path_buf, root_dir)?,
+                                        LINE_SEPARATOR);
+            headers.push(member_string);
+        }
+
         let mut customized_strings = vec![];
         for (name, value) in &self.customized {
             let customized_header_string = format!("{} [{}]", begin_mark, name);
@@ -85,7 +151,17 @@ impl WisphaDirectEntry {
             counter += 1;
         }
 
-        let properties_string = self.properties.to_string(depth, root_dir)?;
+        let mut properties_string = self.properties.to_string(depth, root_dir)?;
+
+        let dependency_header_string = format!("{} [{}]", begin_mark, DEPENDENCY_HEADER);
+        for dependency_path_buf in &*self.dependency_path_bufs.lock().unwrap() {
+            let dependency_string = format!("{}{}{}{}",
+                                            dependency_header_string,
+                                            LINE_SEPARATOR,
+                                            path_string(dependency_path_buf, root_dir)?,
+                                            LINE_SEPARATOR);
+            properties_string = [properties_string, dependency_string].join(LINE_SEPARATOR);
+        }
 
         let mut sub_entry_strings: Vec<String> = Vec::new();
         let sub_entries_header_string = format!("{} [{}]", begin_mark, SUB_ENTRIES_HEADER);
@@ -101,7 +177,7 @@ impl WisphaDirectEntry {
                     format!("{}{}{}{}",
                             entry_file_path_header_string,
                             LINE_SEPARATOR,
-                            entry.entry_file_path.to_str().ok_or(GeneratorError::NameNotValid(entry.entry_file_path.clone()))?,
+                            path_string(&entry.entry_file_path, root_dir)?,
                             LINE_SEPARATOR)
                 }
                 WisphaIntermediateEntry::Direct(entry) => {
@@ -0,0 +1,227 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use std::fs;
+
+use serde_json::Value;
+
+use crate::wispha::{intermediate::*, core::*};
+use crate::generator::error::GeneratorError;
+
+type Result<T> = std::result::Result<T, GeneratorError>;
+
+// fields of classic tags files which name the enclosing tag
+const SCOPE_FIELDS: [&str; 10] = ["class", "struct", "union", "enum", "namespace", "module", "interface", "function", "method", "scope"];
+
+// long names of single-letter kinds in classic tags files, which are the same in most languages
+const CLASSIC_KINDS: [(&str, &str); 16] = [
+    ("c", "class"), ("d", "macro"), ("e", "enumerator"), ("f", "function"),
+    ("g", "enum"), ("i", "interface"), ("m", "member"), ("M", "module"),
+    ("n", "namespace"), ("p", "prototype"), ("P", "package"), ("s", "struct"),
+    ("t", "typedef"), ("u", "union"), ("v", "variable"), ("x", "externvar"),
+];
+
+pub struct Tag {
+    pub name: String,
+    pub kind: Option<String>,
+    pub line: Option<usize>,
+    pub end: Option<usize>,
+    pub scope: Option<String>, // name of the enclosing tag, possibly qualified
+}
+
+// tags of each file, keyed by canonical path. Both universal-ctags JSON output and classic tags files are read,
+// line by line, and relative paths are relative to the directory of the tags file
+pub fn read_tags(path: &PathBuf) -> Result<HashMap<PathBuf, Vec<Tag>>> {
+    let content = fs::read_to_string(path).or(Err(GeneratorError::FileCannotRead(path.clone())))?;
+    let tags_dir = path.parent().map(|parent| parent.to_path_buf()).unwrap_or(PathBuf::new());
+    let mut tags: HashMap<PathBuf, Vec<Tag>> = HashMap::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with("!_") {
+            continue;
+        }
+        let tag = if line.trim_start().starts_with('{') {
+            json_tag_of_line(line)
+        } else {
+            classic_tag_of_line(line)
+        };
+        let (file, tag) = match tag {
+            Some(Some(tag)) => tag,
+            Some(None) => continue,
+            None => return Err(GeneratorError::InvalidTagsLine(path.clone(), index + 1)),
+        };
+        // tags of files which no longer exist are of no use
+        if let Ok(file) = tags_dir.join(file).canonicalize() {
+            tags.entry(file).or_insert_with(Vec::new).push(tag);
+        }
+    }
+    Ok(tags)
+}
+
+// `None` if the line is invalid, and `Some(None)` if it is not a tag, such as pseudo tags
+fn json_tag_of_line(line: &str) -> Option<Option<(String, Tag)>> {
+    let value: Value = serde_json::from_str(line).ok()?;
+    if value.get("_type").and_then(Value::as_str) != Some("tag") {
+        return Some(None);
+    }
+    let name = value.get("name")?.as_str()?.to_string();
+    let file = value.get("path")?.as_str()?.to_string();
+    let string_of = |key: &str| value.get(key).and_then(Value::as_str).map(|value| value.to_string());
+    let number_of = |key: &str| value.get(key).and_then(Value::as_u64).map(|value| value as usize);
+    Some(Some((file, Tag {
+        name,
+        kind: string_of("kind"),
+        line: number_of("line"),
+        end: number_of("end"),
+        scope: string_of("scope"),
+    })))
+}
+
+// `name<TAB>file<TAB>address;"<TAB>fields`, where the address is a line number or a search pattern
+fn classic_tag_of_line(line: &str) -> Option<Option<(String, Tag)>> {
+    let mut parts = line.splitn(3, '\t');
+    let name = parts.next()?.to_string();
+    let file = parts.next()?.to_string();
+    let rest = parts.next()?;
+    let (address, fields) = match rest.rfind(";\"\t") {
+        Some(index) => (&rest[..index], &rest[index + 3..]),
+        None => (rest.trim_end_matches(";\""), ""),
+    };
+    let mut tag = Tag {
+        name,
+        kind: None,
+        line: address.parse().ok(),
+        end: None,
+        scope: None,
+    };
+    for field in fields.split('\t').filter(|field| !field.is_empty()) {
+        match field.find(':') {
+            Some(index) => {
+                let (key, value) = (&field[..index], &field[index + 1..]);
+                match key {
+                    "kind" => tag.kind = Some(long_kind(value)),
+                    "line" => tag.line = value.parse().ok().or(tag.line),
+                    "end" => tag.end = value.parse().ok(),
+                    // `scope:class:Foo` names the kind of the enclosing tag first
+                    "scope" => tag.scope = Some(value.splitn(2, ':').last().unwrap().to_string()),
+                    _ if SCOPE_FIELDS.contains(&key) => tag.scope = Some(value.to_string()),
+                    _ => {},
+                }
+            },
+            // a field without a key is the kind
+            None => tag.kind = Some(long_kind(field)),
+        }
+    }
+    Some(Some((file, tag)))
+}
+
+fn long_kind(kind: &str) -> String {
+    CLASSIC_KINDS.iter()
+        .find(|(letter, _)| *letter == kind)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or(kind.to_string())
+}
+
+// tags nested by scope. The enclosing tag is the closest one before with the last name of the scope.
+// Tags whose enclosing tags are not found are named after their scopes, such as `Foo::bar`
+pub fn program_entries_of_tags(path: &PathBuf, tags: &Vec<Tag>) -> Vec<Arc<Mutex<WisphaIntermediateEntry>>> {
+    let mut order: Vec<usize> = (0..tags.len()).collect();
+    order.sort_by_key(|index| (tags[*index].line.unwrap_or(0), *index));
+    let mut children: Vec<Vec<usize>> = tags.iter().map(|_| vec![]).collect();
+    let mut roots = vec![];
+    for (position, index) in order.iter().enumerate() {
+        let parent = tags[*index].scope.as_ref().and_then(|scope| {
+            let scope_name = last_name_of_scope(scope);
+            order[..position].iter().rev().find(|candidate| tags[**candidate].name == scope_name)
+        });
+        match parent {
+            Some(parent) => children[*parent].push(*index),
+            None => roots.push(*index),
+        }
+    }
+    roots.iter().map(|index| {
+        let entry = program_entry_of_tag(*index, tags, &children, path);
+        if let Some(scope) = &tags[*index].scope {
+            let mut entry = entry.lock().unwrap();
+            let properties = &mut entry.get_direct_entry_mut().unwrap().properties;
+            let separator = if scope.contains('.') && !scope.contains(':') { "." } else { "::" };
+            properties.symbol = Some(properties.name.clone());
+            properties.name = format!("{}{}{}", scope, separator, properties.name);
+        }
+        entry
+    }).collect()
+}
+
+fn last_name_of_scope(scope: &str) -> &str {
+    scope.rsplit(|c| c == ':' || c == '.').next().unwrap_or(scope)
+}
+
+fn program_entry_of_tag(index: usize, tags: &Vec<Tag>, children: &Vec<Vec<usize>>, path: &PathBuf) -> Arc<Mutex<WisphaIntermediateEntry>> {
+    let tag = &tags[index];
+    let mut entry = WisphaDirectEntry::default();
+    entry.properties.entry_type = WisphaEntryType::ProgramEntry;
+    entry.properties.name = tag.name.clone();
+    entry.properties.kind = tag.kind.clone();
+    entry.properties.absolute_path = path.clone();
+    entry.properties.line_range = tag.line.filter(|line| *line > 0)
+        .map(|line| (line, tag.end.unwrap_or(line).max(line)));
+    entry.sub_entries = Mutex::new(children[index].iter()
+        .map(|child| program_entry_of_tag(*child, tags, children, path))
+        .collect());
+    Arc::new(Mutex::new(WisphaIntermediateEntry::Direct(entry)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(name: &str, line: usize, scope: Option<&str>) -> Tag {
+        Tag {
+            name: name.to_string(),
+            kind: None,
+            line: Some(line),
+            end: None,
+            scope: scope.map(|scope| scope.to_string()),
+        }
+    }
+
+    fn properties_of(entry: &Arc<Mutex<WisphaIntermediateEntry>>) -> WisphaEntryProperties {
+        entry.lock().unwrap().get_direct_entry().unwrap().properties.clone()
+    }
+
+    #[test]
+    fn reads_classic_tag_with_fields() {
+        let (file, tag) = classic_tag_of_line("area\tsrc/shape.cc\t12;\"\tf\tclass:Shape\tend:20").unwrap().unwrap();
+        assert_eq!(file, "src/shape.cc");
+        assert_eq!(tag.name, "area");
+        assert_eq!(tag.kind, Some("function".to_string()));
+        assert_eq!((tag.line, tag.end), (Some(12), Some(20)));
+        assert_eq!(tag.scope, Some("Shape".to_string()));
+    }
+
+    #[test]
+    fn reads_classic_tag_with_pattern_address() {
+        let (_, tag) = classic_tag_of_line("Shape\tshape.h\t/^class Shape {$/;\"\tkind:c\tline:3\tscope:namespace:geo").unwrap().unwrap();
+        assert_eq!(tag.kind, Some("class".to_string()));
+        assert_eq!(tag.line, Some(3));
+        assert_eq!(tag.scope, Some("geo".to_string()));
+        assert!(classic_tag_of_line("no fields").is_none());
+    }
+
+    #[test]
+    fn nests_tags_by_scope() {
+        let tags = vec![tag("area", 5, Some("Shape")), tag("Shape", 1, None), tag("draw", 9, Some("Canvas"))];
+        let entries = program_entries_of_tags(&PathBuf::from("/shape.cc"), &tags);
+        assert_eq!(entries.len(), 2);
+        let shape = properties_of(&entries[0]);
+        assert_eq!(shape.name, "Shape");
+        assert_eq!(shape.line_range, Some((1, 1)));
+        let locked_shape = entries[0].lock().unwrap();
+        let area = properties_of(&locked_shape.get_direct_entry().unwrap().sub_entries.lock().unwrap()[0]);
+        assert_eq!(area.name, "area");
+        // the enclosing tag is not found, so the tag is named after its scope
+        let draw = properties_of(&entries[1]);
+        assert_eq!(draw.name, "Canvas::draw");
+        assert_eq!(draw.symbol, Some("draw".to_string()));
+    }
+}
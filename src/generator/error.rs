@@ -4,6 +4,9 @@ use std::fmt::{Display, Formatter, Debug};
 use std::path::PathBuf;
 
 use crate::helper::thread_pool::ThreadPoolError;
+use crate::config_reader::error::ConfigError;
+use crate::parser::error::ParserError;
+use crate::parser::option::ParserOptionError;
 
 #[derive(Debug)]
 pub enum GeneratorError {
@@ -13,7 +16,14 @@ pub enum GeneratorError {
     NameNotValid(PathBuf),
     IgnoreError(ignore::Error),
     FileCannotWrite(PathBuf),
+    FileCannotRead(PathBuf),
     ThreadPoolError(ThreadPoolError),
+    CanNotOpenGitRepository(PathBuf),
+    ConfigError(ConfigError),
+    ParserError(ParserError),
+    ParserOptionError(ParserOptionError),
+    InvalidTagsLine(PathBuf, usize),
+    RuleEntryTypeContradicted(PathBuf, String),
     Unexpected,
 }
 
@@ -44,9 +54,30 @@ impl Display for GeneratorError {
             FileCannotWrite(path) => {
                 format!("Cannot write to file {}. Permission denied.", path.to_str().unwrap())
             },
+            FileCannotRead(path) => {
+                format!("Cannot read file {}.", path.to_str().unwrap())
+            },
             ThreadPoolError(error) => {
                 format!("{}", error)
-            }
+            },
+            CanNotOpenGitRepository(path) => {
+                format!("Cannot open git repository at {}", path.to_str().unwrap())
+            },
+            ConfigError(error) => {
+                format!("{}", error)
+            },
+            ParserError(error) => {
+                format!("{}", error)
+            },
+            ParserOptionError(error) => {
+                format!("{}", error)
+            },
+            InvalidTagsLine(path, line_number) => {
+                format!("Line {} of tags file {} is invalid.", line_number, path.to_str().unwrap())
+            },
+            RuleEntryTypeContradicted(path, entry_type) => {
+                format!("Rules set entry type {} of {}, which contradicts the file system.", entry_type, path.to_str().unwrap())
+            },
         };
         write!(f, "{}", error_message)
     }
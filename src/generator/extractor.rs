@@ -0,0 +1,206 @@
+use std::path::Path;
+use std::collections::HashMap;
+use std::fs;
+
+// extract the description of a file from its content
+pub trait DescriptionExtractor: Send + Sync {
+    fn extract(&self, content: &str) -> Option<String>;
+}
+
+// extractors of files are looked up by extension, and directories are described by their README
+pub struct ExtractorRegistry {
+    extractors: HashMap<String, Box<dyn DescriptionExtractor>>,
+    readme_extractor: Box<dyn DescriptionExtractor>,
+}
+
+impl ExtractorRegistry {
+    pub fn default() -> ExtractorRegistry {
+        let mut registry = ExtractorRegistry {
+            extractors: HashMap::new(),
+            readme_extractor: Box::new(MarkdownParagraphExtractor),
+        };
+        registry.register("rs", Box::new(RustDocExtractor));
+        registry.register("py", Box::new(PythonDocstringExtractor));
+        for extension in &["c", "cc", "cpp", "cxx", "h", "hh", "hpp", "hxx"] {
+            registry.register(extension, Box::new(DoxygenFileExtractor));
+        }
+        registry
+    }
+
+    pub fn register(&mut self, extension: &str, extractor: Box<dyn DescriptionExtractor>) {
+        self.extractors.insert(extension.to_string(), extractor);
+    }
+
+    // `None` if there is no extractor for `path`, or nothing is extracted
+    pub fn extract(&self, path: &Path) -> Option<String> {
+        if path.is_dir() {
+            for readme_name in &["README.md", "readme.md", "Readme.md"] {
+                let readme_path = path.join(readme_name);
+                if readme_path.is_file() {
+                    let content = fs::read_to_string(readme_path).ok()?;
+                    return self.readme_extractor.extract(&content);
+                }
+            }
+            None
+        } else {
+            let extension = path.extension()?.to_str()?;
+            let extractor = self.extractors.get(extension)?;
+            let content = fs::read_to_string(path).ok()?;
+            extractor.extract(&content)
+        }
+    }
+}
+
+// `//!` comments and `/*! */` blocks at the top of the file
+struct RustDocExtractor;
+
+impl DescriptionExtractor for RustDocExtractor {
+    fn extract(&self, content: &str) -> Option<String> {
+        let mut lines = vec![];
+        let mut in_block = false;
+        for line in content.lines() {
+            let trimmed = line.trim();
+            if in_block {
+                if let Some(index) = trimmed.find("*/") {
+                    lines.push(strip_block_line(&trimmed[..index]));
+                    in_block = false;
+                } else {
+                    lines.push(strip_block_line(trimmed));
+                }
+            } else if trimmed.starts_with("//!") {
+                lines.push(strip_one_space(&trimmed[3..]));
+            } else if trimmed.starts_with("/*!") {
+                let rest = &trimmed[3..];
+                if let Some(index) = rest.find("*/") {
+                    lines.push(rest[..index].trim().to_string());
+                } else {
+                    lines.push(rest.trim().to_string());
+                    in_block = true;
+                }
+            } else if trimmed.is_empty() && lines.is_empty() {
+                continue;
+            } else {
+                break;
+            }
+        }
+        join_description(lines)
+    }
+}
+
+// module docstring, which may follow a shebang, comments and blank lines
+struct PythonDocstringExtractor;
+
+impl DescriptionExtractor for PythonDocstringExtractor {
+    fn extract(&self, content: &str) -> Option<String> {
+        let mut lines = content.lines();
+        let (quote, first_line) = loop {
+            let trimmed = lines.next()?.trim();
+            if trimmed.is_empty() || trimmed.starts_with("#") {
+                continue;
+            }
+            let unprefixed = trimmed.trim_start_matches(|c| "rRuU".contains(c));
+            let quote = if unprefixed.starts_with("\"\"\"") {
+                "\"\"\""
+            } else if unprefixed.starts_with("'''") {
+                "'''"
+            } else {
+                return None;
+            };
+            break (quote, &unprefixed[3..]);
+        };
+
+        if let Some(index) = first_line.find(quote) {
+            return join_description(vec![first_line[..index].trim().to_string()]);
+        }
+        let mut docstring_lines = vec![first_line.trim().to_string()];
+        for line in lines {
+            if let Some(index) = line.find(quote) {
+                docstring_lines.push(line[..index].trim().to_string());
+                break;
+            }
+            docstring_lines.push(line.trim().to_string());
+        }
+        join_description(docstring_lines)
+    }
+}
+
+// the first `/** */` or `/*! */` block at the top of the file containing `@file` or `\file`
+struct DoxygenFileExtractor;
+
+impl DescriptionExtractor for DoxygenFileExtractor {
+    fn extract(&self, content: &str) -> Option<String> {
+        let mut lines = content.lines();
+        loop {
+            let trimmed = lines.next()?.trim();
+            if trimmed.is_empty() || trimmed.starts_with("//") {
+                continue;
+            }
+            if !trimmed.starts_with("/*") {
+                return None;
+            }
+            let is_doc = trimmed.starts_with("/**") || trimmed.starts_with("/*!");
+            let mut block_lines = vec![];
+            let mut rest = Some(trimmed[3.min(trimmed.len())..].to_string());
+            while let Some(line) = rest {
+                if let Some(index) = line.find("*/") {
+                    block_lines.push(strip_block_line(&line[..index]));
+                    break;
+                }
+                block_lines.push(strip_block_line(&line));
+                rest = lines.next().map(|line| line.trim().to_string());
+            }
+            let is_file_block = block_lines.iter().any(|line| line.starts_with("@file") || line.starts_with("\\file"));
+            if is_doc && is_file_block {
+                let description_lines = block_lines.into_iter()
+                    .filter(|line| !line.starts_with("@file") && !line.starts_with("\\file"))
+                    .map(|line| {
+                        for tag in &["@brief", "\\brief"] {
+                            if line.starts_with(tag) {
+                                return line[tag.len()..].trim().to_string();
+                            }
+                        }
+                        line
+                    })
+                    .collect();
+                return join_description(description_lines);
+            }
+        }
+    }
+}
+
+// the first paragraph which is not a heading
+struct MarkdownParagraphExtractor;
+
+impl DescriptionExtractor for MarkdownParagraphExtractor {
+    fn extract(&self, content: &str) -> Option<String> {
+        let mut lines = vec![];
+        for line in content.lines() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with("#") {
+                if lines.is_empty() {
+                    continue;
+                }
+                break;
+            }
+            lines.push(trimmed.to_string());
+        }
+        join_description(lines)
+    }
+}
+
+fn strip_one_space(line: &str) -> String {
+    line.strip_prefix(" ").unwrap_or(line).trim_end().to_string()
+}
+
+// strip the leading `*` of lines in comment blocks
+fn strip_block_line(line: &str) -> String {
+    let trimmed = line.trim();
+    trimmed.strip_prefix("*").unwrap_or(trimmed).trim().to_string()
+}
+
+// `None` if all lines are blank. Leading and trailing blank lines are removed
+fn join_description(lines: Vec<String>) -> Option<String> {
+    let first = lines.iter().position(|line| !line.trim().is_empty())?;
+    let last = lines.iter().rposition(|line| !line.trim().is_empty())?;
+    Some(lines[first..=last].join("\n"))
+}
@@ -0,0 +1,188 @@
+use ignore::gitignore::Gitignore;
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+
+// bytes inspected to determine whether a file is binary
+const BINARY_DETECTION_LENGTH: usize = 8000;
+
+// metadata of a file, or aggregated metadata of files in a directory
+#[derive(Clone)]
+pub struct Metadata {
+    pub size: u64,
+    pub lines: Option<usize>, // `None` for binary files
+    pub files: Option<usize>, // only for directories
+    pub language: Option<String>, // only for files
+    pub executable: Option<bool>, // only for files
+}
+
+// metadata collected so far, keyed by path, so that each file is read only once
+// however deep it is and whichever thread needs it. `None` for paths without metadata
+pub type MetadataCache = Mutex<HashMap<PathBuf, Option<Metadata>>>;
+
+// ignored, hidden and `.wispha` files are excluded from aggregation, the same as `generate`.
+// Only regular files, and symbolic links to them, are read. Other files, such as FIFOs and broken links,
+// and files which cannot be read have no metadata, and are not counted in their directories
+pub fn collect(path: &Path, ignored: &Gitignore, allow_hidden_files: bool, wispha_name: &str, cache: &MetadataCache) -> Option<Metadata> {
+    if let Some(metadata) = cache.lock().unwrap().get(path) {
+        return metadata.clone();
+    }
+    let file_type = fs::symlink_metadata(path).ok()?.file_type();
+    let is_regular_file = file_type.is_file()
+        || (file_type.is_symlink() && fs::metadata(path).map(|metadata| metadata.is_file()).unwrap_or(false));
+    let metadata = if file_type.is_dir() {
+        collect_from_directory(path, ignored, allow_hidden_files, wispha_name, cache)
+    } else if is_regular_file {
+        collect_from_file(path).ok()
+    } else {
+        None
+    };
+    cache.lock().unwrap().insert(path.to_path_buf(), metadata.clone());
+    metadata
+}
+
+fn collect_from_file(path: &Path) -> io::Result<Metadata> {
+    let content = fs::read(path)?;
+    Ok(Metadata {
+        size: content.len() as u64,
+        lines: count_lines(&content),
+        files: None,
+        language: detect_language(path, &content),
+        executable: Some(is_executable(path)?),
+    })
+}
+
+fn collect_from_directory(dir: &Path, ignored: &Gitignore, allow_hidden_files: bool, wispha_name: &str, cache: &MetadataCache) -> Option<Metadata> {
+    let mut metadata = Metadata {
+        size: 0,
+        lines: Some(0),
+        files: Some(0),
+        language: None,
+        executable: None,
+    };
+    for entry in fs::read_dir(dir).ok()? {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(_) => continue,
+        };
+        let path = entry.path();
+        // the file type of a directory entry is that of the link itself
+        let is_dir = entry.file_type().map(|file_type| file_type.is_dir()).unwrap_or(false);
+        if ignored.matched(&path, is_dir).is_ignore() {
+            continue;
+        }
+        if entry.file_name().to_str().map(|s| s.starts_with(".")).unwrap_or(false) && !allow_hidden_files {
+            continue;
+        }
+        if entry.file_name().to_str() == Some(wispha_name) && !is_dir {
+            continue;
+        }
+        let sub_metadata = match collect(&path, ignored, allow_hidden_files, wispha_name, cache) {
+            Some(sub_metadata) => sub_metadata,
+            None => continue,
+        };
+        metadata.size += sub_metadata.size;
+        metadata.files = metadata.files.map(|files| files + sub_metadata.files.unwrap_or(1));
+        if let Some(lines) = sub_metadata.lines {
+            metadata.lines = metadata.lines.map(|total| total + lines);
+        }
+    }
+    Some(metadata)
+}
+
+fn count_lines(content: &[u8]) -> Option<usize> {
+    let head = &content[..content.len().min(BINARY_DETECTION_LENGTH)];
+    if head.contains(&0) {
+        return None;
+    }
+    let mut lines = content.iter().filter(|byte| **byte == b'\n').count();
+    if content.last().map(|byte| *byte != b'\n').unwrap_or(false) {
+        lines += 1;
+    }
+    Some(lines)
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> io::Result<bool> {
+    use std::os::unix::fs::PermissionsExt;
+    Ok(fs::metadata(path)?.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> io::Result<bool> {
+    Ok(false)
+}
+
+// detected from extension first, then from shebang
+fn detect_language(path: &Path, content: &[u8]) -> Option<String> {
+    let extension = path.extension().and_then(|extension| extension.to_str()).map(|extension| extension.to_lowercase());
+    if let Some(language) = extension.and_then(|extension| language_of_extension(&extension)) {
+        return Some(language.to_string());
+    }
+
+    let first_line = content.split(|byte| *byte == b'\n').next()?;
+    let first_line = std::str::from_utf8(first_line).ok()?;
+    if !first_line.starts_with("#!") {
+        return None;
+    }
+    let mut words = first_line[2..].split_whitespace();
+    let mut interpreter = words.next()?.rsplit('/').next()?;
+    if interpreter == "env" {
+        interpreter = words.find(|word| !word.starts_with("-"))?;
+    }
+    language_of_interpreter(interpreter).map(|language| language.to_string())
+}
+
+fn language_of_extension(extension: &str) -> Option<&'static str> {
+    let language = match extension {
+        "rs" => "Rust",
+        "c" | "h" => "C",
+        "cpp" | "cc" | "cxx" | "hpp" | "hh" | "hxx" => "C++",
+        "m" => "Objective-C",
+        "mm" => "Objective-C++",
+        "swift" => "Swift",
+        "go" => "Go",
+        "java" => "Java",
+        "kt" | "kts" => "Kotlin",
+        "scala" => "Scala",
+        "cs" => "C#",
+        "py" => "Python",
+        "rb" => "Ruby",
+        "php" => "PHP",
+        "pl" | "pm" => "Perl",
+        "lua" => "Lua",
+        "hs" => "Haskell",
+        "js" | "mjs" => "JavaScript",
+        "ts" => "TypeScript",
+        "sh" | "bash" | "zsh" => "Shell",
+        "html" | "htm" => "HTML",
+        "css" => "CSS",
+        "sql" => "SQL",
+        "proto" => "Protocol Buffers",
+        "json" => "JSON",
+        "toml" => "TOML",
+        "yaml" | "yml" => "YAML",
+        "xml" => "XML",
+        "md" => "Markdown",
+        "wispha" => "Wispha",
+        _ => return None,
+    };
+    Some(language)
+}
+
+fn language_of_interpreter(interpreter: &str) -> Option<&'static str> {
+    let language = match interpreter {
+        "sh" | "bash" | "zsh" | "dash" | "ksh" => "Shell",
+        "node" => "JavaScript",
+        "ruby" => "Ruby",
+        "perl" => "Perl",
+        "php" => "PHP",
+        "lua" => "Lua",
+        _ if interpreter.starts_with("python") => "Python",
+        _ => return None,
+    };
+    Some(language)
+}
@@ -4,10 +4,16 @@ use std::sync::{Mutex, Arc, mpsc};
 use std::sync::mpsc::Sender;
 
 use crate::strings::*;
-use crate::wispha::{intermediate::*, core::*};
+use crate::wispha::{intermediate::*, core::*, common::*};
 use crate::helper::thread_pool::ThreadPool;
+use crate::helper::git::GitHistory;
+use crate::helper::fingerprint;
+use crate::parser::{self, option::ParserOptions};
+use crate::annotator;
+use crate::config_reader::{self, AnnotationPreference, inherited_property_names};
 
 use ignore::{gitignore::{GitignoreBuilder, Gitignore}};
+use git2::{Oid, ObjectType};
 
 pub mod error;
 
@@ -15,29 +21,99 @@ use error::GeneratorError;
 
 mod converter;
 
+mod computed;
+
+mod metadata;
+
+mod program;
+
+mod ctags;
+
+pub mod extractor;
+
+use extractor::ExtractorRegistry;
+
 pub mod option;
 
 use option::*;
 use std::io::{stdout, Write};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::cell::RefCell;
 
 pub type Result<T> = std::result::Result<T, GeneratorError>;
 
+// program entries are matched by the path of their file, their name and their kind
+type ProgramEntryKey = (PathBuf, String, Option<String>);
+
+// shared by all threads when generating entries
+pub struct GenerateContext {
+    pub git_history: Option<GitHistory>,
+    pub existing_entries: HashMap<PathBuf, WisphaEntryProperties>, // properties recorded by the `.wispha` files to be overwritten
+    pub existing_ids: HashMap<PathBuf, String>, // ids recorded by the `.wispha` files to be overwritten
+    pub existing_program_entries: HashMap<ProgramEntryKey, WisphaEntryProperties>, // program entries recorded by the `.wispha` files to be overwritten
+    pub tags: HashMap<PathBuf, Vec<ctags::Tag>>, // tags read from the tags file, keyed by canonical path
+    pub extractors: ExtractorRegistry,
+    pub rule_matchers: Vec<Gitignore>, // one for each rule in options
+    pub virtual_entries: Vec<(PathBuf, Arc<Mutex<WisphaIntermediateEntry>>)>, // path of the parent entry and the virtual entry
+    pub metadata_cache: metadata::MetadataCache,
+}
+
+// what the `.wispha` files to be overwritten record
+#[derive(Default)]
+struct ExistingEntries {
+    properties: HashMap<PathBuf, WisphaEntryProperties>,
+    ids: HashMap<PathBuf, String>,
+    program_entries: HashMap<ProgramEntryKey, WisphaEntryProperties>,
+    virtual_entries: Vec<(PathBuf, Arc<Mutex<WisphaIntermediateEntry>>)>,
+}
+
+// state of one generating process, shared by all threads
+struct GenerateJob {
+    root_dir: Arc<PathBuf>,
+    ignored_files: Arc<Gitignore>,
+    context: Arc<GenerateContext>,
+    options: Arc<GeneratorOptions>,
+    wispha_files: Mutex<Vec<(PathBuf, Arc<Mutex<WisphaIntermediateEntry>>)>>, // entries written to their own `.wispha` files, which can only be written after all their inlined subtrees are constructed
+    thread_pool: Arc<Mutex<ThreadPool>>,
+}
+
 // treat `path` as root. `path` is absolute
 pub fn generate(path: PathBuf, options: GeneratorOptions) -> Result<()> {
     let thread_pool = Arc::new(Mutex::new(ThreadPool::new(options.threads)?));
-    match &options.layer {
-        GenerateLayer::Flat => {
-            let ignored_files = get_ignored_files_from_root(path.clone(), options.ignored_files.clone())?;
-            generate_entry_from_path_flat_and_concurrently(Arc::new(path.clone()), Arc::new(path.clone()), Arc::new(ignored_files), Arc::new(options), thread_pool)?;
-        }
-        GenerateLayer::Recursive => {
-            let ignored_files = get_ignored_files_from_root(path.clone(), options.ignored_files.clone())?;
-            generate_entry_from_path_recursively_and_concurrently(Arc::new(path.clone()), Arc::new(path.clone()), Arc::new(ignored_files), Arc::new(options), thread_pool)?;
-        }
-    }
+    let existing = get_existing_entries(&path, &options)?;
+    let tags = match &options.ctags {
+        Some(ctags) => ctags::read_tags(ctags)?,
+        None => HashMap::new(),
+    };
+    let context = Arc::new(GenerateContext {
+        git_history: get_git_history_if_needed(&path, &options)?,
+        existing_entries: existing.properties,
+        existing_ids: existing.ids,
+        existing_program_entries: existing.program_entries,
+        tags,
+        extractors: ExtractorRegistry::default(),
+        rule_matchers: get_rule_matchers_from_root(&path, &options.rules)?,
+        virtual_entries: existing.virtual_entries,
+        metadata_cache: Mutex::new(HashMap::new()),
+    });
+    let ignored_files = get_ignored_files_from_root(path.clone(), options.ignored_files.clone())?;
+    generate_entry_from_path_concurrently(Arc::new(path.clone()), Arc::new(path.clone()), Arc::new(ignored_files), Arc::clone(&context), Arc::new(options), thread_pool)?;
     Ok(())
 }
 
+// existing `.wispha` files are parsed with the config in `root_dir`, and invalid values in them are overwritten
+fn get_parser_options(root_dir: &PathBuf, options: &GeneratorOptions) -> Result<ParserOptions> {
+    let mut parser_options = ParserOptions::default();
+    let config = config_reader::read_configs_in_dir(root_dir).or_else(|error| Err(GeneratorError::ConfigError(error)))?;
+    if let Some(config) = config {
+        parser_options.update_from_config(&config).or_else(|error| Err(GeneratorError::ParserOptionError(error)))?;
+    }
+    parser_options.check_values = false;
+    parser_options.threads = options.threads;
+    Ok(parser_options)
+}
+
 // read ignored patterns from GeneratorOptions to form a Gitignore instance
 fn get_ignored_files_from_root(root_dir: PathBuf, ignored_files: Vec<String>) -> Result<Gitignore> {
     let mut ignore_builder = GitignoreBuilder::new(root_dir);
@@ -48,8 +124,145 @@ fn get_ignored_files_from_root(root_dir: PathBuf, ignored_files: Vec<String>) ->
     Ok(wispha_ignore)
 }
 
+// rules are matched with the same gitignore-style matcher as ignored files
+fn get_rule_matchers_from_root(root_dir: &PathBuf, rules: &Vec<GenerateRule>) -> Result<Vec<Gitignore>> {
+    let mut rule_matchers = vec![];
+    for rule in rules {
+        rule_matchers.push(get_ignored_files_from_root(root_dir.clone(), vec![rule.pattern.clone()])?);
+    }
+    Ok(rule_matchers)
+}
+
+// only walk the git history when some computed property needs it
+fn get_git_history_if_needed(root_dir: &PathBuf, options: &GeneratorOptions) -> Result<Option<GitHistory>> {
+    if options.computed_properties.iter().any(|(_, source)| source.requires_git()) {
+        let git_history = GitHistory::from_path(root_dir)
+            .or(Err(GeneratorError::CanNotOpenGitRepository(root_dir.clone())))?;
+        Ok(Some(git_history))
+    } else {
+        Ok(None)
+    }
+}
+
+// existing values take precedence over extracted ones, and over annotations if the `.wispha` file is preferred,
+// so they are only needed in these cases.
+// Ids, virtual entries and what is written for program entries cannot be generated from the file system,
+// so they are always kept if the existing files can be parsed.
+// When assigning ids, the existing ones must be known to avoid replacing them
+fn get_existing_entries(root_dir: &PathBuf, options: &GeneratorOptions) -> Result<ExistingEntries> {
+    let mut existing = ExistingEntries::default();
+    let wispha_path = root_dir.join(&options.wispha_name);
+    if !wispha_path.is_file() {
+        return Ok(existing);
+    }
+    let is_needed = options.extract_descriptions || options.annotation_preference == AnnotationPreference::Wispha;
+    let is_required = is_needed || options.assign_ids;
+    let parser_options = get_parser_options(root_dir, options)?;
+    let root = match parser::parse(&wispha_path, parser_options) {
+        Ok(root) => root,
+        Err(error) => {
+            return if is_required {
+                Err(GeneratorError::ParserError(error))
+            } else {
+                Ok(existing)
+            };
+        }
+    };
+    if is_needed {
+        push_into_existing_entries(&root, &mut existing.properties);
+    }
+    push_into_existing_ids(&root, &mut existing.ids);
+    push_into_existing_program_entries(&root, &mut existing.program_entries);
+    push_into_virtual_entries(&root, &mut existing.virtual_entries);
+    Ok(existing)
+}
+
+// ids of virtual entries are kept with the entries themselves, and those of program entries with their other properties
+fn push_into_existing_ids(entry: &Rc<RefCell<WisphaEntry>>, existing_ids: &mut HashMap<PathBuf, String>) {
+    let entry = entry.borrow();
+    if let (Some(id), true) = (&entry.properties.id, entry.properties.has_own_path()) {
+        existing_ids.insert(entry.properties.absolute_path.clone(), id.clone());
+    }
+    for sub_entry in &*entry.sub_entries.borrow() {
+        push_into_existing_ids(sub_entry, existing_ids);
+    }
+}
+
+fn push_into_existing_entries(entry: &Rc<RefCell<WisphaEntry>>, existing_entries: &mut HashMap<PathBuf, WisphaEntryProperties>) {
+    let entry = entry.borrow();
+    if entry.properties.has_own_path() {
+        existing_entries.insert(entry.properties.absolute_path.clone(), entry.properties.clone());
+    }
+    for sub_entry in &*entry.sub_entries.borrow() {
+        push_into_existing_entries(sub_entry, existing_entries);
+    }
+}
+
+// the first of program entries with the same name and kind is kept
+fn push_into_existing_program_entries(entry: &Rc<RefCell<WisphaEntry>>, existing_program_entries: &mut HashMap<ProgramEntryKey, WisphaEntryProperties>) {
+    let entry = entry.borrow();
+    let properties = &entry.properties;
+    if properties.entry_type == WisphaEntryType::ProgramEntry {
+        let key = (properties.absolute_path.clone(), properties.name.clone(), properties.kind.clone());
+        existing_program_entries.entry(key).or_insert_with(|| properties.clone());
+    }
+    for sub_entry in &*entry.sub_entries.borrow() {
+        push_into_existing_program_entries(sub_entry, existing_program_entries);
+    }
+}
+
+// virtual entries directly under entries backed by the file system, with their whole subtrees
+fn push_into_virtual_entries(entry: &Rc<RefCell<WisphaEntry>>, virtual_entries: &mut Vec<(PathBuf, Arc<Mutex<WisphaIntermediateEntry>>)>) {
+    let entry = entry.borrow();
+    for sub_entry in &*entry.sub_entries.borrow() {
+        if sub_entry.borrow().properties.is_virtual() {
+            virtual_entries.push((entry.properties.absolute_path.clone(), intermediate_entry_of(sub_entry)));
+        } else {
+            push_into_virtual_entries(sub_entry, virtual_entries);
+        }
+    }
+}
+
+fn intermediate_entry_of(entry: &Rc<RefCell<WisphaEntry>>) -> Arc<Mutex<WisphaIntermediateEntry>> {
+    let entry = entry.borrow();
+    let mut direct_entry = WisphaDirectEntry::default();
+    direct_entry.properties = entry.properties.clone();
+    direct_entry.dependency_path_bufs = Mutex::new(entry.dependency_path_bufs.borrow().clone());
+    direct_entry.sub_entries = Mutex::new(entry.sub_entries.borrow().iter().map(intermediate_entry_of).collect());
+    Arc::new(Mutex::new(WisphaIntermediateEntry::Direct(direct_entry)))
+}
+
+// put each virtual entry back under the entry at its recorded parent path, or under the root entry if there is none
+fn attach_virtual_entries(wispha_files: &Vec<(PathBuf, Arc<Mutex<WisphaIntermediateEntry>>)>,
+                          virtual_entries: &Vec<(PathBuf, Arc<Mutex<WisphaIntermediateEntry>>)>) {
+    for (parent_path, virtual_entry) in virtual_entries {
+        let parent = wispha_files.iter()
+            .find_map(|(_, entry)| find_intermediate_entry_by_path(entry, parent_path))
+            .unwrap_or(Arc::clone(&wispha_files[0].1));
+        let mut locked_parent = parent.lock().unwrap();
+        if let Some(direct_parent) = locked_parent.get_direct_entry_mut() {
+            direct_parent.sub_entries.lock().unwrap().push(Arc::clone(virtual_entry));
+        }
+    }
+}
+
+// link entries are not followed, since their files are in `wispha_files` too
+fn find_intermediate_entry_by_path(entry: &Arc<Mutex<WisphaIntermediateEntry>>, path: &PathBuf) -> Option<Arc<Mutex<WisphaIntermediateEntry>>> {
+    let locked_entry = entry.lock().unwrap();
+    let direct_entry = locked_entry.get_direct_entry()?;
+    if &direct_entry.properties.absolute_path == path && direct_entry.properties.has_own_path() {
+        return Some(Arc::clone(entry));
+    }
+    for sub_entry in &*direct_entry.sub_entries.lock().unwrap() {
+        if let Some(found) = find_intermediate_entry_by_path(sub_entry, path) {
+            return Some(found);
+        }
+    }
+    None
+}
+
 // `path` is absolute
-fn generate_file_at_path_without_sub_and_sup(path: Arc<PathBuf>, options: Arc<GeneratorOptions>) -> Result<WisphaDirectEntry> {
+fn generate_file_at_path_without_sub_and_sup(path: Arc<PathBuf>, ignored_files: Arc<Gitignore>, context: Arc<GenerateContext>, options: Arc<GeneratorOptions>) -> Result<WisphaDirectEntry> {
     let mut wispha_entry = WisphaDirectEntry::default();
 
     wispha_entry.properties.name = path.file_name().ok_or(GeneratorError::NameNotDetermined((*path).clone()))?
@@ -58,26 +271,136 @@ fn generate_file_at_path_without_sub_and_sup(path: Arc<PathBuf>, options: Arc<Ge
 
     wispha_entry.properties.absolute_path = (*path).clone();
 
+    wispha_entry.properties.id = context.existing_ids.get(&*path).cloned();
+
     wispha_entry.properties.entry_type = match path.is_dir() {
         true => WisphaEntryType::Directory,
         false => WisphaEntryType::File,
     };
 
+    // values of later rules take precedence
+    let mut rule_properties = HashMap::new();
+    for (rule, matcher) in options.rules.iter().zip(context.rule_matchers.iter()) {
+        if matcher.matched_path_or_any_parents(&*path, path.is_dir()).is_ignore() {
+            if let Some(entry_type) = &rule.entry_type {
+                // built-in types must agree with the file system
+                let contradicted = match entry_type {
+                    WisphaEntryType::Directory => !path.is_dir(),
+                    WisphaEntryType::File => path.is_dir(),
+                    _ => false,
+                };
+                if contradicted {
+                    return Err(GeneratorError::RuleEntryTypeContradicted((*path).clone(), entry_type.to_str().to_string()));
+                }
+                wispha_entry.properties.entry_type = entry_type.clone();
+            }
+            for (name, value) in &rule.properties {
+                rule_properties.insert(name.clone(), value.clone());
+            }
+        }
+    }
+
+    if let Some(existing_properties) = context.existing_entries.get(&*path) {
+        wispha_entry.properties.description = existing_properties.description.clone()
+            .filter(|description| !description.trim().is_empty());
+        // computed properties are always computed again
+        for (name, value) in &existing_properties.customized {
+            if !options.computed_properties.iter().any(|(computed_name, _)| computed_name == name) {
+                wispha_entry.properties.customized.insert(name.clone(), value.clone());
+            }
+        }
+    }
+
+    if path.is_file() {
+        if let Some(annotation) = annotator::read_annotation(&path) {
+            annotator::merge_annotation(&mut wispha_entry.properties, &annotation, &options.properties, options.annotation_preference);
+        }
+    }
+
+    if options.extract_descriptions && wispha_entry.properties.description.is_none() {
+        wispha_entry.properties.description = context.extractors.extract(&path);
+    }
+
+    if options.fingerprint {
+        let fingerprint = fingerprint::fingerprint_of_path(&path, &ignored_files, options.allow_hidden_files, &options.wispha_name)
+            .or(Err(GeneratorError::FileCannotRead((*path).clone())))?;
+        wispha_entry.properties.fingerprint = Some(fingerprint);
+    }
+
+    for (name, value) in rule_properties {
+        if !wispha_entry.properties.customized.contains_key(&name) {
+            wispha_entry.properties.customized.insert(name, value);
+        }
+    }
+
     let properties = &options.properties;
     for property in properties {
         if let Some(default_value) = &property.default_value {
-            wispha_entry.properties.customized.insert(property.name.clone(), default_value.clone());
+            if !wispha_entry.properties.customized.contains_key(&property.name) {
+                wispha_entry.properties.customized.insert(property.name.clone(), default_value.clone());
+            }
         }
     }
 
+    let metadata = if options.computed_properties.iter().any(|(_, source)| source.requires_metadata()) {
+        metadata::collect(&path, &ignored_files, options.allow_hidden_files, &options.wispha_name, &context.metadata_cache)
+    } else {
+        None
+    };
+
+    for (name, source) in &options.computed_properties {
+        if let Some(value) = computed::compute(source, &path, &context.git_history, &metadata) {
+            wispha_entry.properties.customized.insert(name.clone(), value);
+        }
+    }
+
+    // files parsed natively do not use tags
+    if path.is_file() {
+        let program_entries = if options.program_entries && program::is_parsed(&path) {
+            program::program_entries_of_file(&path)
+        } else {
+            path.canonicalize().ok()
+                .and_then(|canonical_path| context.tags.get(&canonical_path))
+                .map(|tags| ctags::program_entries_of_tags(&path, tags))
+                .unwrap_or(vec![])
+        };
+        restore_program_entries(&program_entries, &context.existing_program_entries);
+        wispha_entry.sub_entries = Mutex::new(program_entries);
+    }
+
     Ok(wispha_entry)
 }
 
-fn should_include_entry(entry: &DirEntry, wispha_ignore: Arc<Gitignore>, options: Arc<GeneratorOptions>) -> bool {
+// descriptions written by hand take precedence over doc comments
+fn restore_program_entries(entries: &Vec<Arc<Mutex<WisphaIntermediateEntry>>>, existing_program_entries: &HashMap<ProgramEntryKey, WisphaEntryProperties>) {
+    for entry in entries {
+        let mut locked_entry = entry.lock().unwrap();
+        if let Some(direct_entry) = locked_entry.get_direct_entry_mut() {
+            let properties = &mut direct_entry.properties;
+            let key = (properties.absolute_path.clone(), properties.name.clone(), properties.kind.clone());
+            if let Some(existing_properties) = existing_program_entries.get(&key) {
+                properties.id = existing_properties.id.clone();
+                if let Some(description) = existing_properties.description.clone().filter(|description| !description.trim().is_empty()) {
+                    properties.description = Some(description);
+                }
+                properties.symbol = existing_properties.symbol.clone();
+                properties.customized = existing_properties.customized.clone();
+            }
+            restore_program_entries(&*direct_entry.sub_entries.lock().unwrap(), existing_program_entries);
+        }
+    }
+}
+
+fn should_include_entry(entry: &DirEntry, wispha_ignore: &Gitignore, options: &GeneratorOptions) -> bool {
     if wispha_ignore.matched(&entry.path(), entry.path().is_dir()).is_ignore() {
         return false;
     }
 
+    // `.wispha` files are generated, not described
+    if entry.file_name().to_str() == Some(options.wispha_name.as_str()) && !entry.path().is_dir() {
+        return false;
+    }
+
     if entry.file_name().to_str().map(|s| s.starts_with(".")).unwrap_or(false) {
         return options.allow_hidden_files;
     }
@@ -85,106 +408,131 @@ fn should_include_entry(entry: &DirEntry, wispha_ignore: Arc<Gitignore>, options
     true
 }
 
-// Only called once in a parsing process
-// Will not returned until the entire tree is constructed and all `.wispha` files are written
-// `path` and `root_dir` is absolute
-fn generate_entry_from_path_recursively_and_concurrently(path: Arc<PathBuf>,
-                                                         root_dir: Arc<PathBuf>,
-                                                         ignored_files: Arc<Gitignore>,
-                                                         options: Arc<GeneratorOptions>,
-                                                         thread_pool: Arc<Mutex<ThreadPool>>) -> Result<()> {
-    if path.is_dir() {
-        let (tx_global, rx_global) = mpsc::channel(); // `tx_global` will be moved into sub routine
-        let cloned_path = Arc::clone(&path);
-        let cloned_root_dir = Arc::clone(&root_dir);
-        let cloned_ignored_files = Arc::clone(&ignored_files);
-        let cloned_options = Arc::clone(&options);
-        let cloned_thread_pool = Arc::clone(&thread_pool);
-        let cloned_tx_global = Sender::clone(&tx_global);
-        thread_pool.lock().unwrap().execute(move || {
-            let result = generate_entry_from_path_recursively_and_concurrently_sub_routine(cloned_path, cloned_root_dir, cloned_ignored_files, cloned_options, cloned_tx_global, cloned_thread_pool);
-            tx_global.send(result).unwrap();
-        });
-        let mut counter = 0;
-        for result in rx_global {
-            result?;
-            counter += 1;
-            print!("\rRecording {} files.", counter);
-            stdout().flush().unwrap();
+// the layout rule in effect for a directory, the depth of the directory under the rule,
+// and its depth under the root directory
+#[derive(Clone)]
+struct LayoutScope {
+    rule: LayoutRule,
+    depth: usize,
+    absolute_depth: usize,
+    max_absolute_depth: Option<usize>, // `max_depth` of the project, and of overrides counted from their directories
+}
+
+impl LayoutScope {
+    fn of_root(options: &GeneratorOptions) -> LayoutScope {
+        LayoutScope {
+            rule: options.layout.clone(),
+            depth: 0,
+            absolute_depth: 0,
+            max_absolute_depth: options.layout.max_depth,
         }
-        println!();
-        Ok(())
-    } else {
-        Err(GeneratorError::PathIsNotDir((*path).clone()))
     }
 }
 
-// called by `generate_entry_from_path_recursively_and_concurrently` and itself.
-fn generate_entry_from_path_recursively_and_concurrently_sub_routine(path: Arc<PathBuf>,
-                                                                     root_dir: Arc<PathBuf>,
-                                                                     ignored_files: Arc<Gitignore>,
-                                                                     options: Arc<GeneratorOptions>,
-                                                                     tx_global: Sender<Result<()>>,
-                                                                     thread_pool: Arc<Mutex<ThreadPool>>) -> Result<()> {
-    let direct_entry = Arc::new(Mutex::new(generate_file_at_path_without_sub_and_sup(Arc::clone(&path), Arc::clone(&options))?));
+// a directory starts a new scope if it is overridden in options with a different layout
+fn scope_of_sub_directory(path: &PathBuf, scope: &LayoutScope, root_dir: &PathBuf, options: &GeneratorOptions) -> LayoutScope {
+    let relative_path = path.strip_prefix(root_dir).unwrap_or(path);
+    let absolute_depth = scope.absolute_depth + 1;
+    match options.layout_overrides.iter().find(|(override_path, _)| override_path == relative_path) {
+        Some((_, layout_override)) => {
+            let rule = scope.rule.overridden_by(layout_override);
+            let max_absolute_depth = match (scope.max_absolute_depth, layout_override.max_depth) {
+                (Some(max_absolute_depth), Some(max_depth)) => Some(max_absolute_depth.min(absolute_depth + max_depth)),
+                (None, Some(max_depth)) => Some(absolute_depth + max_depth),
+                (max_absolute_depth, None) => max_absolute_depth,
+            };
+            let depth = if rule.has_same_layout(&scope.rule) { scope.depth + 1 } else { 0 };
+            LayoutScope {
+                rule,
+                depth,
+                absolute_depth,
+                max_absolute_depth,
+            }
+        },
+        None => LayoutScope {
+            rule: scope.rule.clone(),
+            depth: scope.depth + 1,
+            absolute_depth,
+            max_absolute_depth: scope.max_absolute_depth,
+        },
+    }
+}
 
-    // this function is designed to be called with `path` as directory
-    let entries = fs::read_dir(&*path).or(Err(GeneratorError::DirCannotRead((*path).clone())))?;
-    for entry in entries {
-        let entry = entry.or(Err(GeneratorError::Unexpected))?;
-        let cloned_ignored_files = Arc::clone(&ignored_files);
-        let cloned_options = Arc::clone(&options);
-        let cloned_path = Arc::new(entry.path().clone());
-        let cloned_root_dir = Arc::clone(&root_dir);
-        let cloned_thread_pool = Arc::clone(&thread_pool);
-        let cloned_tx_global = Sender::clone(&tx_global);
-        if should_include_entry(&entry, Arc::clone(&cloned_ignored_files), Arc::clone(&cloned_options)) {
+// `count_files_up_to(limit)` counts files in the directory, and may stop counting when `limit` is reached
+fn has_own_wispha_file<F>(scope: &LayoutScope, count_files_up_to: F) -> bool
+    where   F: FnOnce(usize) -> usize
+{
+    if scope.depth == 0 {
+        return true;
+    }
+    match &scope.rule.layer {
+        GenerateLayer::Flat => false,
+        GenerateLayer::Recursive => true,
+        GenerateLayer::Hybrid => {
+            if scope.depth <= scope.rule.hybrid_levels {
+                return true;
+            }
+            match scope.rule.hybrid_min_files {
+                Some(min_files) => count_files_up_to(min_files) >= min_files,
+                None => false,
+            }
+        }
+    }
+}
+
+// stop counting when `limit` is reached, since only whether a directory is large matters
+fn count_files_in_dir_up_to(dir: &PathBuf, limit: usize, ignored_files: &Gitignore, options: &GeneratorOptions) -> usize {
+    let mut count = 0;
+    let mut dirs = vec![dir.clone()];
+    while let Some(dir) = dirs.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            if !should_include_entry(&entry, ignored_files, options) {
+                continue;
+            }
             if entry.path().is_dir() {
-                let link_entry = WisphaLinkEntry {
-                    entry_file_path: entry.path().clone().join(PathBuf::from(&options.wispha_name)),
-                };
-                direct_entry.lock().unwrap().sub_entries.lock().unwrap().push(Arc::new(Mutex::new(WisphaIntermediateEntry::Link(link_entry))));
-                thread_pool.lock().unwrap().execute(move || {
-                    let tx_global = cloned_tx_global;
-                    let result = generate_entry_from_path_recursively_and_concurrently_sub_routine(cloned_path, cloned_root_dir, cloned_ignored_files, cloned_options, Sender::clone(&tx_global), cloned_thread_pool);
-                    tx_global.send(result).unwrap();
-                });
+                dirs.push(entry.path());
             } else {
-                let sub_entry = generate_file_at_path_without_sub_and_sup(Arc::new(entry.path()), Arc::clone(&options))?;
-                direct_entry.lock().unwrap().sub_entries.lock().unwrap().push(Arc::new(Mutex::new(WisphaIntermediateEntry::Direct(sub_entry))));
-                tx_global.send(Ok(())).unwrap();
+                count += 1;
+                if count >= limit {
+                    return count;
+                }
             }
         }
     }
-    let locked_entry = direct_entry.lock().unwrap();
-    let absolute_path = path.join(PathBuf::from(&options.wispha_name));
-    fs::write(&absolute_path, locked_entry.to_file_string(0, &root_dir)?)
-        .or(Err(GeneratorError::FileCannotWrite(absolute_path.clone())))?;
-    drop(locked_entry);
-    Ok(())
+    count
 }
 
-// Only called once in a parsing process
-// Will not returned until the entire tree is constructed and `.wispha` files are written
+// Only called once in a generating process
+// Will not returned until the entire tree is constructed and all `.wispha` files are written
 // `path` and `root_dir` is absolute
-fn generate_entry_from_path_flat_and_concurrently(path: Arc<PathBuf>,
-                                                  root_dir: Arc<PathBuf>,
-                                                  ignored_files: Arc<Gitignore>,
-                                                  options: Arc<GeneratorOptions>,
-                                                  thread_pool: Arc<Mutex<ThreadPool>>) -> Result<()> {
+fn generate_entry_from_path_concurrently(path: Arc<PathBuf>,
+                                         root_dir: Arc<PathBuf>,
+                                         ignored_files: Arc<Gitignore>,
+                                         context: Arc<GenerateContext>,
+                                         options: Arc<GeneratorOptions>,
+                                         thread_pool: Arc<Mutex<ThreadPool>>) -> Result<()> {
     if path.is_dir() {
-        let this_entry = Arc::new(Mutex::new(WisphaIntermediateEntry::Direct(generate_file_at_path_without_sub_and_sup(Arc::clone(&path), Arc::clone(&options))?)));
+        let root_entry = Arc::new(Mutex::new(WisphaIntermediateEntry::Direct(generate_file_at_path_without_sub_and_sup(Arc::clone(&path), Arc::clone(&ignored_files), Arc::clone(&context), Arc::clone(&options))?)));
+        let job = Arc::new(GenerateJob {
+            root_dir: Arc::clone(&root_dir),
+            ignored_files: Arc::clone(&ignored_files),
+            context: Arc::clone(&context),
+            options: Arc::clone(&options),
+            wispha_files: Mutex::new(vec![(path.join(&options.wispha_name), Arc::clone(&root_entry))]),
+            thread_pool: Arc::clone(&thread_pool),
+        });
+        let scope = LayoutScope::of_root(&options);
         let (tx_global, rx_global) = mpsc::channel();
-        let cloned_wispha = Arc::clone(&this_entry);
-        let cloned_ignored_files = Arc::clone(&ignored_files);
-        let cloned_options = Arc::clone(&options);
         let cloned_path = Arc::clone(&path);
-        let cloned_root_dir = Arc::clone(&root_dir);
+        let cloned_job = Arc::clone(&job);
         let cloned_tx_global = Sender::clone(&tx_global);
-        let cloned_thread_pool = Arc::clone(&thread_pool);
         thread_pool.lock().unwrap().execute(move || {
             let tx_global = cloned_tx_global;
-            let result = generate_entry_from_path_flat_and_concurrently_sub_routine(cloned_path, cloned_root_dir, cloned_ignored_files, cloned_options, cloned_wispha, Sender::clone(&tx_global), cloned_thread_pool);
+            let result = generate_entry_from_path_concurrently_sub_routine(cloned_path, scope, root_entry, cloned_job, Sender::clone(&tx_global));
             tx_global.send(result).unwrap();
         });
         drop(tx_global);
@@ -196,47 +544,283 @@ fn generate_entry_from_path_flat_and_concurrently(path: Arc<PathBuf>,
             stdout().flush().unwrap();
         }
         println!();
-        let absolute_path = this_entry.lock().unwrap().get_direct_entry().unwrap().properties.absolute_path.join(&options.wispha_name);
-        fs::write(&absolute_path, this_entry.lock().unwrap().get_direct_entry().unwrap().to_file_string(0, &root_dir)?).or(Err(GeneratorError::FileCannotWrite(absolute_path.clone())))?;
+        let wispha_files = &job.wispha_files;
+        attach_virtual_entries(&wispha_files.lock().unwrap(), &context.virtual_entries);
+        if options.assign_ids {
+            assign_ids(&wispha_files.lock().unwrap(), &root_dir);
+        }
+        let inherited_properties = inherited_property_names(&options.properties);
+        if !inherited_properties.is_empty() {
+            let wispha_files = wispha_files.lock().unwrap();
+            remove_redundant_inherited_values(&wispha_files[0].1, &HashMap::new(), &wispha_files, &inherited_properties);
+        }
+        for (absolute_path, entry) in &*wispha_files.lock().unwrap() {
+            let file_string = entry.lock().unwrap().get_direct_entry().ok_or(GeneratorError::Unexpected)?.to_file_string(0, &root_dir)?;
+            fs::write(absolute_path, file_string).or(Err(GeneratorError::FileCannotWrite(absolute_path.clone())))?;
+        }
         Ok(())
     } else {
         Err(GeneratorError::PathIsNotDir((*path).clone()))
     }
 }
 
-// called by `generate_entry_from_path_flat_and_concurrently` and itself.
-fn generate_entry_from_path_flat_and_concurrently_sub_routine(path: Arc<PathBuf>,
-                                                              root_dir: Arc<PathBuf>,
-                                                              ignored_files: Arc<Gitignore>,
-                                                              options: Arc<GeneratorOptions>,
-                                                              this_entry: Arc<Mutex<WisphaIntermediateEntry>>,
-                                                              tx_global: mpsc::Sender<Result<()>>,
-                                                              thread_pool: Arc<Mutex<ThreadPool>>) -> Result<()> {
+// give each entry without an id a new one. Ids are derived from the paths of entries when assigned,
+// and never change afterwards
+fn assign_ids(wispha_files: &Vec<(PathBuf, Arc<Mutex<WisphaIntermediateEntry>>)>, root_dir: &PathBuf) {
+    let mut used_ids = HashSet::new();
+    for (_, entry) in wispha_files {
+        push_into_used_ids(entry, &mut used_ids);
+    }
+    for (_, entry) in wispha_files {
+        assign_ids_in_entry(entry, &PathBuf::new(), root_dir, &mut used_ids);
+    }
+}
+
+// link entries are not followed, since their files are in `wispha_files` too
+fn push_into_used_ids(entry: &Arc<Mutex<WisphaIntermediateEntry>>, used_ids: &mut HashSet<String>) {
+    let locked_entry = entry.lock().unwrap();
+    if let Some(direct_entry) = locked_entry.get_direct_entry() {
+        if let Some(id) = &direct_entry.properties.id {
+            used_ids.insert(id.clone());
+        }
+        for sub_entry in &*direct_entry.sub_entries.lock().unwrap() {
+            push_into_used_ids(sub_entry, used_ids);
+        }
+    }
+}
+
+// `parent_path` is the relative path of the parent entry, which names entries without their own paths
+fn assign_ids_in_entry(entry: &Arc<Mutex<WisphaIntermediateEntry>>, parent_path: &PathBuf, root_dir: &PathBuf, used_ids: &mut HashSet<String>) {
+    let mut locked_entry = entry.lock().unwrap();
+    let direct_entry = match locked_entry.get_direct_entry_mut() {
+        Some(direct_entry) => direct_entry,
+        None => return,
+    };
+    let path = if !direct_entry.properties.has_own_path() {
+        parent_path.join(&direct_entry.properties.name)
+    } else {
+        direct_entry.properties.absolute_path.strip_prefix(root_dir).unwrap_or(&direct_entry.properties.absolute_path).to_path_buf()
+    };
+    if direct_entry.properties.id.is_none() {
+        let id = new_id(&path.to_string_lossy(), used_ids);
+        used_ids.insert(id.clone());
+        direct_entry.properties.id = Some(id);
+    }
+    for sub_entry in &*direct_entry.sub_entries.lock().unwrap() {
+        assign_ids_in_entry(sub_entry, &path, root_dir, used_ids);
+    }
+}
+
+// a short hash of `seed`, which is hashed again with a counter if taken
+fn new_id(seed: &str, used_ids: &HashSet<String>) -> String {
+    let mut counter = 0;
+    loop {
+        let salted = if counter == 0 { seed.to_string() } else { format!("{}#{}", seed, counter) };
+        let hash = Oid::hash_object(ObjectType::Blob, salted.as_bytes())
+            .map(|oid| oid.to_string())
+            .unwrap_or(salted);
+        let id: String = hash.chars().take(ID_LENGTH).collect();
+        if !used_ids.contains(&id) {
+            return id;
+        }
+        counter += 1;
+    }
+}
+
+// values of inherited properties equal to the values inherited from parent entries are not written.
+// `inherited` contains the values in effect for the parent of `entry`
+fn remove_redundant_inherited_values(entry: &Arc<Mutex<WisphaIntermediateEntry>>,
+                                     inherited: &HashMap<String, String>,
+                                     wispha_files: &Vec<(PathBuf, Arc<Mutex<WisphaIntermediateEntry>>)>,
+                                     names: &Vec<String>) {
+    let mut locked_entry = entry.lock().unwrap();
+    let direct_entry = match locked_entry.get_direct_entry_mut() {
+        Some(direct_entry) => direct_entry,
+        None => return,
+    };
+    let mut in_effect = inherited.clone();
+    for name in names {
+        match direct_entry.properties.customized.get(name) {
+            Some(value) if inherited.get(name) == Some(value) => {
+                direct_entry.properties.customized.remove(name);
+            },
+            Some(value) => {
+                in_effect.insert(name.clone(), value.clone());
+            },
+            None => {},
+        }
+    }
+    for sub_entry in &*direct_entry.sub_entries.lock().unwrap() {
+        let linked_entry = sub_entry.lock().unwrap().get_link_entry()
+            .and_then(|link_entry| wispha_files.iter().find(|(absolute_path, _)| absolute_path == &link_entry.entry_file_path))
+            .map(|(_, linked_entry)| Arc::clone(linked_entry));
+        match linked_entry {
+            Some(linked_entry) => remove_redundant_inherited_values(&linked_entry, &in_effect, wispha_files, names),
+            None => remove_redundant_inherited_values(sub_entry, &in_effect, wispha_files, names),
+        }
+    }
+}
+
+// called by `generate_entry_from_path_concurrently` and itself. `path` is a directory in `scope`,
+// whose entry is `this_entry`
+fn generate_entry_from_path_concurrently_sub_routine(path: Arc<PathBuf>,
+                                                     scope: LayoutScope,
+                                                     this_entry: Arc<Mutex<WisphaIntermediateEntry>>,
+                                                     job: Arc<GenerateJob>,
+                                                     tx_global: mpsc::Sender<Result<()>>) -> Result<()> {
+    let (root_dir, ignored_files, context, options) = (&job.root_dir, &job.ignored_files, &job.context, &job.options);
+    if scope.max_absolute_depth.map(|max_depth| scope.absolute_depth >= max_depth).unwrap_or(false) {
+        return Ok(());
+    }
     let entries = fs::read_dir(&*path).or(Err(GeneratorError::DirCannotRead((*path).clone())))?;
     for entry in entries {
         let entry = entry.or(Err(GeneratorError::Unexpected))?;
-        let cloned_wispha = Arc::clone(&this_entry);
-        let cloned_ignored_files = Arc::clone(&ignored_files);
-        let cloned_options = Arc::clone(&options);
-        let cloned_path = Arc::new(entry.path().clone());
-        let cloned_root_dir = Arc::clone(&root_dir);
-        let cloned_tx_global = Sender::clone(&tx_global);
-        let cloned_thread_pool = Arc::clone(&thread_pool);
-        if should_include_entry(&entry, Arc::clone(&cloned_ignored_files), Arc::clone(&cloned_options)) {
-            if entry.path().is_dir() {
-                let sub_entry = Arc::new(Mutex::new(WisphaIntermediateEntry::Direct(generate_file_at_path_without_sub_and_sup(Arc::clone(&cloned_path), Arc::clone(&options))?)));
-                this_entry.lock().unwrap().get_direct_entry_mut().unwrap().sub_entries.lock().unwrap().push(Arc::clone(&sub_entry));
-                thread_pool.lock().unwrap().execute(move || {
-                    let tx_global = cloned_tx_global;
-                    let result = generate_entry_from_path_flat_and_concurrently_sub_routine(cloned_path, cloned_root_dir, cloned_ignored_files, cloned_options, cloned_wispha, Sender::clone(&tx_global), cloned_thread_pool);
-                    tx_global.send(result).unwrap();
-                });
+        if !should_include_entry(&entry, &ignored_files, &options) {
+            continue;
+        }
+        let entry_path = Arc::new(entry.path());
+        let sub_entry = Arc::new(Mutex::new(WisphaIntermediateEntry::Direct(generate_file_at_path_without_sub_and_sup(Arc::clone(&entry_path), Arc::clone(ignored_files), Arc::clone(context), Arc::clone(options))?)));
+        if entry_path.is_dir() {
+            let sub_scope = scope_of_sub_directory(&entry_path, &scope, root_dir, options);
+            if has_own_wispha_file(&sub_scope, |limit| count_files_in_dir_up_to(&entry_path, limit, ignored_files, options)) {
+                let entry_file_path = entry_path.join(&options.wispha_name);
+                let link_entry = WisphaLinkEntry {
+                    entry_file_path: entry_file_path.clone(),
+                };
+                this_entry.lock().unwrap().get_direct_entry_mut().unwrap().sub_entries.lock().unwrap().push(Arc::new(Mutex::new(WisphaIntermediateEntry::Link(link_entry))));
+                job.wispha_files.lock().unwrap().push((entry_file_path, Arc::clone(&sub_entry)));
             } else {
-                let sub_entry = generate_file_at_path_without_sub_and_sup(Arc::clone(&path), Arc::clone(&options))?;
-                this_entry.lock().unwrap().get_direct_entry_mut().unwrap().sub_entries.lock().unwrap().push(Arc::new(Mutex::new(WisphaIntermediateEntry::Direct(sub_entry))));
-                tx_global.send(Ok(())).unwrap();
+                this_entry.lock().unwrap().get_direct_entry_mut().unwrap().sub_entries.lock().unwrap().push(Arc::clone(&sub_entry));
             }
+            let cloned_job = Arc::clone(&job);
+            let cloned_tx_global = Sender::clone(&tx_global);
+            job.thread_pool.lock().unwrap().execute(move || {
+                let tx_global = cloned_tx_global;
+                let result = generate_entry_from_path_concurrently_sub_routine(entry_path, sub_scope, sub_entry, cloned_job, Sender::clone(&tx_global));
+                tx_global.send(result).unwrap();
+            });
+        } else {
+            this_entry.lock().unwrap().get_direct_entry_mut().unwrap().sub_entries.lock().unwrap().push(sub_entry);
+            tx_global.send(Ok(())).unwrap();
         }
     }
     Ok(())
 }
+
+// rewrite the tree recorded by the `.wispha` file at `path` with the layout in `options`, keeping all properties.
+// `.wispha` files no longer used are removed. Entries are never dropped, so `max_depth` is ignored
+pub fn restructure(path: PathBuf, options: GeneratorOptions) -> Result<()> {
+    let root_dir = path.parent().unwrap().to_path_buf();
+    let mut parser_options = get_parser_options(&root_dir, &options)?;
+    parser_options.keep_undeclared_properties = true;
+    let root = parser::parse(&path, parser_options).or_else(|error| Err(GeneratorError::ParserError(error)))?;
+
+    let mut old_wispha_files = vec![];
+    get_wispha_files_from_root(&root, &mut old_wispha_files);
+
+    let scope = LayoutScope::of_root(&options);
+    let mut wispha_files = vec![];
+    let root_entry = restructure_entry(&root, &scope, &root_dir, &options, &mut wispha_files);
+    wispha_files.insert(0, (path.clone(), root_entry));
+
+    for (absolute_path, entry) in &wispha_files {
+        let file_string = entry.lock().unwrap().get_direct_entry().ok_or(GeneratorError::Unexpected)?.to_file_string(0, &root_dir)?;
+        fs::write(absolute_path, file_string).or(Err(GeneratorError::FileCannotWrite(absolute_path.clone())))?;
+    }
+    for old_wispha_file in old_wispha_files {
+        if !wispha_files.iter().any(|(absolute_path, _)| absolute_path == &old_wispha_file) {
+            fs::remove_file(&old_wispha_file).or(Err(GeneratorError::FileCannotWrite(old_wispha_file.clone())))?;
+        }
+    }
+    Ok(())
+}
+
+fn get_wispha_files_from_root(root: &Rc<RefCell<WisphaEntry>>, wispha_files: &mut Vec<PathBuf>) {
+    let file_path = root.borrow().properties.file_path.clone();
+    if !wispha_files.contains(&file_path) {
+        wispha_files.push(file_path);
+    }
+    for sub_entry in &*root.borrow().sub_entries.borrow() {
+        get_wispha_files_from_root(sub_entry, wispha_files);
+    }
+}
+
+// convert `entry` back to an intermediate entry. Directories which have their own `.wispha` files
+// are replaced by links, and pushed into `wispha_files`
+fn restructure_entry(entry: &Rc<RefCell<WisphaEntry>>,
+                     scope: &LayoutScope,
+                     root_dir: &PathBuf,
+                     options: &GeneratorOptions,
+                     wispha_files: &mut Vec<(PathBuf, Arc<Mutex<WisphaIntermediateEntry>>)>) -> Arc<Mutex<WisphaIntermediateEntry>> {
+    let entry = entry.borrow();
+    let mut direct_entry = WisphaDirectEntry::default();
+    direct_entry.properties = entry.properties.clone();
+    direct_entry.dependency_path_bufs = Mutex::new(entry.dependency_path_bufs.borrow().clone());
+    let mut sub_entries = vec![];
+    for sub_entry in &*entry.sub_entries.borrow() {
+        let sub_path = sub_entry.borrow().properties.absolute_path.clone();
+        let is_directory = match sub_entry.borrow().properties.entry_type {
+            WisphaEntryType::Directory | WisphaEntryType::Custom(_) => sub_path.is_dir(),
+            _ => false,
+        };
+        if is_directory {
+            let sub_scope = scope_of_sub_directory(&sub_path, scope, root_dir, options);
+            let restructured = restructure_entry(sub_entry, &sub_scope, root_dir, options, wispha_files);
+            if has_own_wispha_file(&sub_scope, |limit| count_file_entries_up_to(sub_entry, limit)) {
+                let entry_file_path = sub_path.join(&options.wispha_name);
+                wispha_files.push((entry_file_path.clone(), restructured));
+                sub_entries.push(Arc::new(Mutex::new(WisphaIntermediateEntry::Link(WisphaLinkEntry {
+                    entry_file_path,
+                }))));
+            } else {
+                sub_entries.push(restructured);
+            }
+        } else {
+            sub_entries.push(restructure_entry(sub_entry, scope, root_dir, options, wispha_files));
+        }
+    }
+    direct_entry.sub_entries = Mutex::new(sub_entries);
+    Arc::new(Mutex::new(WisphaIntermediateEntry::Direct(direct_entry)))
+}
+
+fn count_file_entries_up_to(entry: &Rc<RefCell<WisphaEntry>>, limit: usize) -> usize {
+    let mut count = 0;
+    for sub_entry in &*entry.borrow().sub_entries.borrow() {
+        if count >= limit {
+            break;
+        }
+        let entry_type = sub_entry.borrow().properties.entry_type.clone();
+        let is_directory = sub_entry.borrow().properties.absolute_path.is_dir();
+        count += match entry_type {
+            WisphaEntryType::File => 1,
+            WisphaEntryType::Directory => count_file_entries_up_to(sub_entry, limit - count),
+            WisphaEntryType::Custom(_) if is_directory => count_file_entries_up_to(sub_entry, limit - count),
+            WisphaEntryType::Custom(_) => 1,
+            _ => 0,
+        };
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_id_is_stable_and_short() {
+        let id = new_id("src/parser", &HashSet::new());
+        assert_eq!(id.len(), ID_LENGTH);
+        assert_eq!(id, new_id("src/parser", &HashSet::new()));
+        assert_ne!(id, new_id("src/generator", &HashSet::new()));
+    }
+
+    #[test]
+    fn new_id_avoids_used_ids() {
+        let mut used_ids = HashSet::new();
+        let first = new_id("src/parser", &used_ids);
+        used_ids.insert(first.clone());
+        let second = new_id("src/parser", &used_ids);
+        assert_ne!(first, second);
+        assert_eq!(second.len(), ID_LENGTH);
+    }
+}
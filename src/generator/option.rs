@@ -1,35 +1,166 @@
 use std::error::Error;
 use std::fmt;
 use std::fmt::{Display, Formatter, Debug};
+use std::path::PathBuf;
+use std::collections::HashMap;
 
-use crate::commandline::Generate;
-use crate::config_reader::{Config, PropertyConfig};
+use crate::commandline::{Generate, Restructure};
+use crate::config_reader::{Config, PropertyConfig, AnnotationPreference, custom_entry_type_names};
 use crate::strings::*;
+use crate::wispha::core::WisphaEntryType;
 
 type Result<T> = std::result::Result<T, GeneratorOptionError>;
 
 #[derive(Clone)]
 pub struct GeneratorOptions {
-    pub layer: GenerateLayer,
+    pub layout: LayoutRule,
+    pub layout_overrides: Vec<(PathBuf, LayoutOverride)>, // relative path of directory and how its subtree is laid out
     pub allow_hidden_files: bool,
+    pub fingerprint: bool,
+    pub extract_descriptions: bool,
+    pub assign_ids: bool, // entries without ids are given new ones
+    pub program_entries: bool, // public items in source files become program entries
+    pub ctags: Option<PathBuf>, // tags file whose tags become program entries of files not parsed natively
+    pub annotation_preference: AnnotationPreference,
     pub properties: Vec<PropertyConfig>,
+    pub rules: Vec<GenerateRule>, // later rules take precedence
+    pub custom_entry_types: Vec<String>,
+    pub computed_properties: Vec<(String, PropertySource)>, // name of property and where its value comes from
     pub ignored_files: Vec<String>,
     pub wispha_name: String,
     pub threads: usize,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum GenerateLayer {
     Flat,
     Recursive,
+    Hybrid,
+}
+
+impl GenerateLayer {
+    pub fn from(layer: &str) -> Option<GenerateLayer> {
+        match layer {
+            FLAT_LAYER => Some(GenerateLayer::Flat),
+            RECURSIVE_LAYER => Some(GenerateLayer::Recursive),
+            HYBRID_LAYER => Some(GenerateLayer::Hybrid),
+            _ => None,
+        }
+    }
+}
+
+// how directories of a subtree are laid out in `.wispha` files.
+// The directory which the rule applies to is at depth 0, and always has its own `.wispha` file
+#[derive(Clone)]
+pub struct LayoutRule {
+    pub layer: GenerateLayer,
+    pub max_depth: Option<usize>, // entries deeper than this are not recorded
+    pub hybrid_levels: usize, // in hybrid layer, directories not deeper than this have their own `.wispha` files
+    pub hybrid_min_files: Option<usize>, // in hybrid layer, directories containing at least this number of files also have their own
+}
+
+// fields that are `None` are inherited from the rule of the parent directory
+#[derive(Clone)]
+pub struct LayoutOverride {
+    pub layer: Option<GenerateLayer>,
+    pub max_depth: Option<usize>,
+    pub hybrid_levels: Option<usize>,
+    pub hybrid_min_files: Option<usize>,
+}
+
+impl LayoutRule {
+    pub fn overridden_by(&self, layout_override: &LayoutOverride) -> LayoutRule {
+        LayoutRule {
+            layer: layout_override.layer.unwrap_or(self.layer),
+            max_depth: layout_override.max_depth.or(self.max_depth),
+            hybrid_levels: layout_override.hybrid_levels.unwrap_or(self.hybrid_levels),
+            hybrid_min_files: layout_override.hybrid_min_files.or(self.hybrid_min_files),
+        }
+    }
+
+    // whether directories get their own `.wispha` files the same way under both rules. `max_depth` is not part of it
+    pub fn has_same_layout(&self, other: &LayoutRule) -> bool {
+        match (self.layer, other.layer) {
+            (GenerateLayer::Hybrid, GenerateLayer::Hybrid) => self.hybrid_levels == other.hybrid_levels && self.hybrid_min_files == other.hybrid_min_files,
+            (layer, other_layer) => layer == other_layer,
+        }
+    }
+}
+
+// default values for entries matching `pattern`, which is relative to the root directory
+#[derive(Clone)]
+pub struct GenerateRule {
+    pub pattern: String,
+    pub properties: HashMap<String, String>,
+    pub entry_type: Option<WisphaEntryType>,
+}
+
+#[derive(Clone, Copy)]
+pub enum PropertySource {
+    GitLastAuthor,
+    GitLastModified,
+    GitCommits,
+    MetadataSize,
+    MetadataLines,
+    MetadataFiles,
+    MetadataLanguage,
+    MetadataExecutable,
+}
+
+impl PropertySource {
+    pub fn from(source: &str) -> Option<PropertySource> {
+        use PropertySource::*;
+        match source {
+            GIT_LAST_AUTHOR_SOURCE => Some(GitLastAuthor),
+            GIT_LAST_MODIFIED_SOURCE => Some(GitLastModified),
+            GIT_COMMITS_SOURCE => Some(GitCommits),
+            METADATA_SIZE_SOURCE => Some(MetadataSize),
+            METADATA_LINES_SOURCE => Some(MetadataLines),
+            METADATA_FILES_SOURCE => Some(MetadataFiles),
+            METADATA_LANGUAGE_SOURCE => Some(MetadataLanguage),
+            METADATA_EXECUTABLE_SOURCE => Some(MetadataExecutable),
+            _ => None,
+        }
+    }
+
+    pub fn requires_git(&self) -> bool {
+        use PropertySource::*;
+        match &self {
+            GitLastAuthor | GitLastModified | GitCommits => true,
+            _ => false,
+        }
+    }
+
+    pub fn requires_metadata(&self) -> bool {
+        use PropertySource::*;
+        match &self {
+            MetadataSize | MetadataLines | MetadataFiles | MetadataLanguage | MetadataExecutable => true,
+            _ => false,
+        }
+    }
 }
 
 impl GeneratorOptions {
     pub fn default() -> GeneratorOptions {
         GeneratorOptions {
-            layer: GenerateLayer::Recursive,
+            layout: LayoutRule {
+                layer: GenerateLayer::Recursive,
+                max_depth: None,
+                hybrid_levels: DEFAULT_HYBRID_LEVELS,
+                hybrid_min_files: None,
+            },
+            layout_overrides: vec![],
             allow_hidden_files: false,
+            fingerprint: false,
+            extract_descriptions: false,
+            assign_ids: false,
+            program_entries: false,
+            ctags: None,
+            annotation_preference: DEFAULT_ANNOTATION_PREFERENCE,
             properties: vec![],
+            rules: vec![],
+            custom_entry_types: vec![],
+            computed_properties: vec![],
             ignored_files: vec![],
             wispha_name: DEFAULT_FILE_NAME_STR.to_string(),
             threads: DEFAULT_THREADS,
@@ -39,20 +170,73 @@ impl GeneratorOptions {
     pub fn update_from_commandline(&mut self, generate: &Generate) -> Result<()> {
         self.validate_commandline(generate)?;
         if generate.recursively {
-            self.layer = GenerateLayer::Recursive;
+            self.layout.layer = GenerateLayer::Recursive;
         }
         if generate.flat {
-            self.layer = GenerateLayer::Flat;
+            self.layout.layer = GenerateLayer::Flat;
         };
+        if generate.hybrid {
+            self.layout.layer = GenerateLayer::Hybrid;
+        }
+        if let Some(max_depth) = generate.max_depth {
+            self.layout.max_depth = Some(max_depth);
+        }
+        if let Some(hybrid_levels) = generate.hybrid_levels {
+            self.layout.hybrid_levels = hybrid_levels;
+        }
+        if let Some(hybrid_min_files) = generate.hybrid_min_files {
+            self.layout.hybrid_min_files = Some(hybrid_min_files);
+        }
         if generate.all {
             self.allow_hidden_files = true;
         }
+        if generate.fingerprint {
+            self.fingerprint = true;
+        }
+        if generate.extract_descriptions {
+            self.extract_descriptions = true;
+        }
+        if generate.assign_ids {
+            self.assign_ids = true;
+        }
+        if generate.program_entries {
+            self.program_entries = true;
+        }
+        if let Some(ctags) = &generate.ctags {
+            self.ctags = Some(ctags.clone());
+        }
         if let Some(threads) = generate.threads {
             self.threads = threads;
         }
         Ok(())
     }
 
+    pub fn update_from_restructure(&mut self, restructure: &Restructure) -> Result<()> {
+        let layer_count = [restructure.flat, restructure.recursively, restructure.hybrid].iter().filter(|flag| **flag).count();
+        if layer_count > 1 {
+            return Err(GeneratorOptionError::MultipleLayers);
+        }
+        if restructure.recursively {
+            self.layout.layer = GenerateLayer::Recursive;
+        }
+        if restructure.flat {
+            self.layout.layer = GenerateLayer::Flat;
+        }
+        if restructure.hybrid {
+            self.layout.layer = GenerateLayer::Hybrid;
+        }
+        if let Some(hybrid_levels) = restructure.hybrid_levels {
+            self.layout.hybrid_levels = hybrid_levels;
+        }
+        if let Some(hybrid_min_files) = restructure.hybrid_min_files {
+            self.layout.hybrid_min_files = Some(hybrid_min_files);
+        }
+        if let Some(threads) = restructure.threads {
+            self.threads = threads;
+        }
+        Ok(())
+    }
+
     pub fn update_from_config(&mut self, config: &Config) -> Result<()> {
         if let Some(generate_config) = &config.generate {
             if let Some(allow_hidden_file) = generate_config.allow_hidden_files {
@@ -64,16 +248,97 @@ impl GeneratorOptions {
             if let Some(wispha_name) = &generate_config.wispha_name {
                 self.wispha_name = wispha_name.clone();
             }
+            if let Some(fingerprint) = generate_config.fingerprint {
+                self.fingerprint = fingerprint;
+            }
+            if let Some(extract_descriptions) = generate_config.extract_descriptions {
+                self.extract_descriptions = extract_descriptions;
+            }
+            if let Some(assign_ids) = generate_config.assign_ids {
+                self.assign_ids = assign_ids;
+            }
+            if let Some(program_entries) = generate_config.program_entries {
+                self.program_entries = program_entries;
+            }
+            if let Some(layer) = &generate_config.layer {
+                self.layout.layer = GenerateLayer::from(layer).ok_or(GeneratorOptionError::UnknownLayer(layer.clone()))?;
+            }
+            if let Some(max_depth) = generate_config.max_depth {
+                self.layout.max_depth = Some(max_depth);
+            }
+            if let Some(hybrid_levels) = generate_config.hybrid_levels {
+                self.layout.hybrid_levels = hybrid_levels;
+            }
+            if let Some(hybrid_min_files) = generate_config.hybrid_min_files {
+                self.layout.hybrid_min_files = Some(hybrid_min_files);
+            }
+            if let Some(overrides) = &generate_config.overrides {
+                self.layout_overrides.clear();
+                for override_config in overrides {
+                    let layer = match &override_config.layer {
+                        Some(layer) => Some(GenerateLayer::from(layer).ok_or(GeneratorOptionError::UnknownLayer(layer.clone()))?),
+                        None => None,
+                    };
+                    self.layout_overrides.push((PathBuf::from(&override_config.path), LayoutOverride {
+                        layer,
+                        max_depth: override_config.max_depth,
+                        hybrid_levels: override_config.hybrid_levels,
+                        hybrid_min_files: override_config.hybrid_min_files,
+                    }));
+                }
+            }
+        }
+        if let Some(annotation_config) = &config.annotation {
+            if let Some(prefer) = annotation_config.prefer {
+                self.annotation_preference = prefer;
+            }
         }
         if let Some(properties) = &config.properties {
             self.properties = properties.clone();
+            self.computed_properties.clear();
+            for property in properties {
+                if let Some(source) = &property.source {
+                    let computed_source = PropertySource::from(source)
+                        .ok_or(GeneratorOptionError::UnknownPropertySource(source.clone()))?;
+                    self.computed_properties.push((property.name.clone(), computed_source));
+                }
+            }
+        }
+        if let Some(entry_types) = &config.entry_types {
+            self.custom_entry_types = custom_entry_type_names(entry_types);
+        }
+        if let Some(rules) = &config.rules {
+            self.rules.clear();
+            for rule in rules {
+                let properties = rule.properties.clone().unwrap_or(HashMap::new());
+                for name in properties.keys() {
+                    if !self.properties.iter().any(|property| &property.name == name) {
+                        return Err(GeneratorOptionError::UndeclaredProperty(name.clone()));
+                    }
+                }
+                let entry_type = match &rule.entry_type {
+                    Some(entry_type) => Some(WisphaEntryType::from_with_custom_types(entry_type.clone(), &self.custom_entry_types)
+                        .ok_or(GeneratorOptionError::UnknownEntryType(entry_type.clone()))?),
+                    None => None,
+                };
+                // only entries backed by the file system are matched by rules
+                if let Some(WisphaEntryType::ProgramEntry) | Some(WisphaEntryType::Virtual) = entry_type {
+                    return Err(GeneratorOptionError::InvalidRuleEntryType(rule.entry_type.clone().unwrap()));
+                }
+                self.rules.push(GenerateRule {
+                    pattern: rule.pattern.clone(),
+                    properties,
+                    entry_type,
+                });
+            }
         }
         Ok(())
     }
 
     fn validate_commandline(&self, generate: &Generate) -> Result<()> {
-        if generate.flat && generate.recursively {
-            return Err(GeneratorOptionError::FlatAndRecursive);
+        let layer_count = [generate.flat, generate.recursively, generate.hybrid].iter().filter(|flag| **flag).count();
+        if layer_count > 1 {
+            return Err(GeneratorOptionError::MultipleLayers);
         }
         Ok(())
     }
@@ -81,7 +346,12 @@ impl GeneratorOptions {
 
 #[derive(Debug)]
 pub enum GeneratorOptionError {
-    FlatAndRecursive,
+    MultipleLayers,
+    UnknownLayer(String),
+    UnknownPropertySource(String),
+    UndeclaredProperty(String),
+    UnknownEntryType(String),
+    InvalidRuleEntryType(String),
 }
 
 impl Error for GeneratorOptionError { }
@@ -90,8 +360,23 @@ impl Display for GeneratorOptionError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         use GeneratorOptionError::*;
         match &self {
-            FlatAndRecursive => {
-                write!(f, "Cannot specify flat and recursively at same time.")
+            MultipleLayers => {
+                write!(f, "Cannot specify more than one of flat, recursively and hybrid at same time.")
+            },
+            UnknownLayer(layer) => {
+                write!(f, "Unknown layer {}. Layer should be flat, recursive or hybrid.", layer)
+            },
+            UnknownPropertySource(source) => {
+                write!(f, "Unknown property source {}.", source)
+            },
+            UndeclaredProperty(name) => {
+                write!(f, "Property {} in rules is not declared in properties.", name)
+            },
+            UnknownEntryType(entry_type) => {
+                write!(f, "Unknown entry type {}.", entry_type)
+            },
+            InvalidRuleEntryType(entry_type) => {
+                write!(f, "Entry type {} cannot be set by rules.", entry_type)
             },
         }
     }
@@ -0,0 +1,103 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::fs;
+
+use syn::{Item, Attribute, Visibility, Meta, Lit};
+use syn::spanned::Spanned;
+use proc_macro2::Span;
+
+use crate::wispha::{intermediate::*, core::*};
+
+// whether program entries of the file come from parsing it
+pub fn is_parsed(path: &PathBuf) -> bool {
+    path.extension().and_then(|extension| extension.to_str()) == Some("rs")
+}
+
+// public items of a Rust file, nested as in the file. Other files and files which cannot be parsed have none
+pub fn program_entries_of_file(path: &PathBuf) -> Vec<Arc<Mutex<WisphaIntermediateEntry>>> {
+    if !is_parsed(path) {
+        return vec![];
+    }
+    let file = match fs::read_to_string(path).ok().and_then(|content| syn::parse_file(&content).ok()) {
+        Some(file) => file,
+        None => return vec![],
+    };
+    program_entries_of_items(&file.items, path)
+}
+
+fn program_entries_of_items(items: &Vec<Item>, path: &PathBuf) -> Vec<Arc<Mutex<WisphaIntermediateEntry>>> {
+    let mut entries = vec![];
+    for item in items {
+        let (kind, name, attrs, vis, sub_items) = match item {
+            Item::Mod(item) => ("module", item.ident.to_string(), &item.attrs, &item.vis, item.content.as_ref().map(|(_, items)| items)),
+            Item::Struct(item) => ("struct", item.ident.to_string(), &item.attrs, &item.vis, None),
+            Item::Enum(item) => ("enum", item.ident.to_string(), &item.attrs, &item.vis, None),
+            Item::Trait(item) => ("trait", item.ident.to_string(), &item.attrs, &item.vis, None),
+            Item::Fn(item) => ("function", item.sig.ident.to_string(), &item.attrs, &item.vis, None),
+            _ => continue,
+        };
+        if let Visibility::Public(_) = vis {
+            let mut entry = WisphaDirectEntry::default();
+            entry.properties.entry_type = WisphaEntryType::ProgramEntry;
+            entry.properties.name = name;
+            entry.properties.kind = Some(kind.to_string());
+            entry.properties.absolute_path = path.clone();
+            entry.properties.description = doc_of(attrs);
+            entry.properties.line_range = Some(line_range_of(item, attrs, vis));
+            if let Some(sub_items) = sub_items {
+                entry.sub_entries = Mutex::new(program_entries_of_items(sub_items, path));
+            }
+            entries.push(Arc::new(Mutex::new(WisphaIntermediateEntry::Direct(entry))));
+        }
+    }
+    entries
+}
+
+// `///` and `/** */` comments are `doc` attributes, with the space after `///` kept
+fn doc_of(attrs: &Vec<Attribute>) -> Option<String> {
+    let lines: Vec<String> = attrs.iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(meta)) => match meta.lit {
+                Lit::Str(doc) => Some(doc.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .map(|doc| text_of_doc(&doc))
+        .collect();
+    let doc = lines.join("\n").trim().to_string();
+    if doc.is_empty() { None } else { Some(doc) }
+}
+
+// the fallback of `proc_macro2` keeps `/** */` comments as they are written
+fn text_of_doc(doc: &str) -> String {
+    if doc.starts_with("/**") && doc.ends_with("*/") {
+        doc[3..doc.len() - 2].lines()
+            .map(|line| {
+                let line = line.trim_start();
+                let line = line.strip_prefix('*').unwrap_or(line);
+                line.strip_prefix(' ').unwrap_or(line).to_string()
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    } else {
+        doc.strip_prefix(' ').unwrap_or(doc).to_string()
+    }
+}
+
+// from the first attribute other than doc comments, or `pub`, to the end of the item
+fn line_range_of(item: &Item, attrs: &Vec<Attribute>, vis: &Visibility) -> (usize, usize) {
+    let first_line = attrs.iter()
+        .filter(|attr| !attr.path.is_ident("doc"))
+        .map(|attr| line_of(attr.span()))
+        .chain(std::iter::once(line_of(vis.span())))
+        .min()
+        .unwrap();
+    let last_line = item.span().end().line;
+    (first_line, last_line.max(first_line))
+}
+
+fn line_of(span: Span) -> usize {
+    span.start().line
+}
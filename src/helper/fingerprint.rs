@@ -0,0 +1,38 @@
+use git2::{Oid, ObjectType};
+use ignore::gitignore::Gitignore;
+
+use std::path::Path;
+use std::fs;
+use std::io;
+
+// The fingerprint of a file is the hash of its content, and that of a directory is
+// the hash of the sorted names of its entries which are neither ignored nor hidden (unless allowed).
+// `.wispha` files named `wispha_name` are skipped, since they are written after fingerprints are computed
+pub fn fingerprint_of_path(path: &Path, ignored: &Gitignore, allow_hidden_files: bool, wispha_name: &str) -> io::Result<String> {
+    let content = if path.is_dir() {
+        let entries = fs::read_dir(path)?;
+        let mut names = vec![];
+        for entry in entries {
+            let entry = entry?;
+            let entry_path = entry.path();
+            if ignored.matched(&entry_path, entry_path.is_dir()).is_ignore() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name == wispha_name && !entry_path.is_dir() {
+                continue;
+            }
+            if name.starts_with(".") && !allow_hidden_files {
+                continue;
+            }
+            names.push(name);
+        }
+        names.sort();
+        names.join("\n").into_bytes()
+    } else {
+        fs::read(path)?
+    };
+    // hashing bytes in memory only fails if libgit2 cannot be initialized
+    let oid = Oid::hash_object(ObjectType::Blob, &content).map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+    Ok(oid.to_string())
+}
@@ -0,0 +1,115 @@
+use git2::{Repository, Sort};
+
+use std::path::{Path, PathBuf};
+use std::collections::{HashMap, HashSet};
+
+type Result<T> = std::result::Result<T, git2::Error>;
+
+#[derive(Clone)]
+pub struct GitCommitRecord {
+    pub author: String,
+    pub time: i64, // seconds since epoch
+    pub offset_minutes: i32,
+}
+
+// commits touching each path in the work directory, newest first.
+// A directory is touched by a commit if any path inside it is touched
+pub struct GitHistory {
+    pub workdir: PathBuf,
+    commits: HashMap<PathBuf, Vec<GitCommitRecord>>,
+}
+
+impl GitHistory {
+    // `path` can be any path inside the work directory of a repository
+    pub fn from_path(path: &Path) -> Result<GitHistory> {
+        let repository = Repository::discover(path)?;
+        let workdir = repository.workdir()
+            .ok_or(git2::Error::from_str("bare repository has no work directory"))?
+            .to_path_buf();
+        let mut revwalk = repository.revwalk()?;
+        revwalk.push_head()?;
+        revwalk.set_sorting(Sort::TIME);
+
+        let mut commits: HashMap<PathBuf, Vec<GitCommitRecord>> = HashMap::new();
+        for id in revwalk {
+            let commit = repository.find_commit(id?)?;
+            // merge commits only bring in changes already recorded by their parents
+            if commit.parent_count() > 1 {
+                continue;
+            }
+            let tree = commit.tree()?;
+            let parent_tree = if commit.parent_count() == 1 {
+                Some(commit.parent(0)?.tree()?)
+            } else {
+                None
+            };
+            let diff = repository.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+
+            let mut touched_paths = HashSet::new();
+            for delta in diff.deltas() {
+                for file in &[delta.old_file(), delta.new_file()] {
+                    if let Some(path) = file.path() {
+                        let mut path = workdir.join(path);
+                        while path.starts_with(&workdir) && touched_paths.insert(path.clone()) {
+                            if !path.pop() {
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+
+            let author = commit.author();
+            let record = GitCommitRecord {
+                author: author.name().unwrap_or("").to_string(),
+                time: author.when().seconds(),
+                offset_minutes: author.when().offset_minutes(),
+            };
+            for path in touched_paths {
+                commits.entry(path).or_insert_with(Vec::new).push(record.clone());
+            }
+        }
+
+        Ok(GitHistory { workdir, commits })
+    }
+
+    // newest first. Empty if the path is not tracked
+    pub fn commits_of(&self, path: &Path) -> &[GitCommitRecord] {
+        self.commits.get(path).map(|commits| commits.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn last_commit_of(&self, path: &Path) -> Option<&GitCommitRecord> {
+        self.commits_of(path).first()
+    }
+}
+
+impl GitCommitRecord {
+    // formatted as `YYYY-MM-DD HH:MM:SS +HHMM` in the author's time zone
+    pub fn time_string(&self) -> String {
+        format_time(self.time, self.offset_minutes)
+    }
+}
+
+pub fn format_time(seconds: i64, offset_minutes: i32) -> String {
+    let local = seconds + offset_minutes as i64 * 60;
+    let days = local.div_euclid(86400);
+    let seconds_of_day = local.rem_euclid(86400);
+
+    // convert days since 1970-01-01 to a civil date
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    let sign = if offset_minutes < 0 { '-' } else { '+' };
+    let offset = offset_minutes.abs();
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02} {}{:02}{:02}",
+            year, month, day,
+            seconds_of_day / 3600, seconds_of_day % 3600 / 60, seconds_of_day % 60,
+            sign, offset / 60, offset % 60)
+}
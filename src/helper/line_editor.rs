@@ -0,0 +1,39 @@
+// edits on the lines of a text. Line numbers start at 1
+pub enum LineEdit {
+    Replace { first: usize, last: usize, lines: Vec<String> }, // replace lines `first..=last`
+    Insert { after: usize, lines: Vec<String> }, // insert after line `after`, 0 to insert at the beginning
+}
+
+impl LineEdit {
+    fn anchor(&self) -> usize {
+        match &self {
+            LineEdit::Replace { first, .. } => *first,
+            LineEdit::Insert { after, .. } => *after + 1,
+        }
+    }
+}
+
+// edits must not overlap
+pub fn apply_line_edits(content: &str, mut edits: Vec<LineEdit>) -> String {
+    let mut lines: Vec<String> = content.lines().map(|line| line.to_string()).collect();
+    // apply from the bottom so that line numbers of remaining edits are still valid
+    edits.sort_by(|a, b| b.anchor().cmp(&a.anchor()));
+    for edit in edits {
+        match edit {
+            LineEdit::Replace { first, last, lines: new_lines } => {
+                let first = (first - 1).min(lines.len());
+                let last = last.min(lines.len()).max(first);
+                lines.splice(first..last, new_lines);
+            },
+            LineEdit::Insert { after, lines: new_lines } => {
+                let after = after.min(lines.len());
+                lines.splice(after..after, new_lines);
+            },
+        }
+    }
+    let mut result = lines.join("\n");
+    if content.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
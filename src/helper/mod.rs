@@ -1 +1,4 @@
-pub mod thread_pool;
\ No newline at end of file
+pub mod thread_pool;
+pub mod git;
+pub mod fingerprint;
+pub mod line_editor;
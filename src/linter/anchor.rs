@@ -0,0 +1,63 @@
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+
+use crate::linter::{LintIssue, issue_of_property};
+use crate::wispha::{common::*, core::line_range_string};
+use crate::strings::*;
+
+// how many lines before and after the recorded range the symbol may be found in
+const NEARBY_LINES: usize = 3;
+
+// lines of files read so far. Files which cannot be read are `None`
+pub type FileLines = RefCell<HashMap<PathBuf, Option<Vec<String>>>>;
+
+// the symbol of a program entry, or its name if not given, is found near its line range.
+// If it is found elsewhere in the file, the range is suggested to start from the nearest occurrence
+pub fn check_line_range(entry: &Rc<RefCell<WisphaEntry>>, file_lines: &FileLines, issues: &mut Vec<LintIssue>) {
+    let borrowed_entry = entry.borrow();
+    let properties = &borrowed_entry.properties;
+    let (first, last) = match properties.line_range {
+        Some(line_range) => line_range,
+        None => return,
+    };
+    let symbol = properties.symbol.as_ref().unwrap_or(&properties.name);
+    let path = &properties.absolute_path;
+    let mut file_lines = file_lines.borrow_mut();
+    let lines = file_lines.entry(path.clone())
+        .or_insert_with(|| fs::read_to_string(path).ok().map(|content| content.lines().map(|line| line.to_string()).collect()));
+    let lines = match lines {
+        Some(lines) => lines,
+        None => {
+            issues.push(issue_of_property(entry, LINE_RANGE_HEADER, format!("File {} of entry {} cannot be read.", path.to_str().unwrap(), properties.name)));
+            return;
+        }
+    };
+
+    let occurrences: Vec<usize> = lines.iter().enumerate()
+        .filter(|(_, line)| contains_symbol(line, symbol))
+        .map(|(index, _)| index + 1)
+        .collect();
+    let is_nearby = |line: &usize| *line + NEARBY_LINES >= first && *line <= last + NEARBY_LINES;
+    if occurrences.iter().any(is_nearby) {
+        return;
+    }
+    let message = match occurrences.iter().min_by_key(|line| (**line as isize - first as isize).abs()) {
+        Some(line) => format!("Symbol {} of entry {} is not found near lines {}. It is found at line {}, so the line range may be {}-{}.",
+                              symbol, properties.name, line_range_string(&(first, last)), line, line, line + last - first),
+        None => format!("Symbol {} of entry {} is not found in file {}.", symbol, properties.name, path.to_str().unwrap()),
+    };
+    issues.push(issue_of_property(entry, LINE_RANGE_HEADER, message));
+}
+
+// the symbol is not part of a longer identifier
+fn contains_symbol(line: &str, symbol: &str) -> bool {
+    let is_identifier_char = |c: char| c.is_alphanumeric() || c == '_';
+    line.match_indices(symbol).any(|(index, _)| {
+        let before = line[..index].chars().next_back();
+        let after = line[index + symbol.len()..].chars().next();
+        !before.map(is_identifier_char).unwrap_or(false) && !after.map(is_identifier_char).unwrap_or(false)
+    })
+}
@@ -0,0 +1,120 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use crate::linter::{LintIssue, issue_of_resolved_dependency, error::LinterError};
+use crate::config_reader::DependencyRuleConfig;
+use crate::wispha::common::*;
+
+type Result<T> = std::result::Result<T, LinterError>;
+
+// `DependencyRuleConfig` with its patterns compiled
+pub struct DependencyRule {
+    from_pattern: String,
+    from: Gitignore,
+    forbidden: Vec<(String, Gitignore)>,
+}
+
+pub fn compile_dependency_rules(root_dir: &PathBuf, configs: &Vec<DependencyRuleConfig>) -> Result<Vec<DependencyRule>> {
+    let mut rules = vec![];
+    for config in configs {
+        let mut forbidden = vec![];
+        for pattern in &config.forbid {
+            forbidden.push((pattern.clone(), matcher_of(root_dir, pattern)?));
+        }
+        rules.push(DependencyRule {
+            from_pattern: config.from.clone(),
+            from: matcher_of(root_dir, &config.from)?,
+            forbidden,
+        });
+    }
+    Ok(rules)
+}
+
+fn matcher_of(root_dir: &PathBuf, pattern: &String) -> Result<Gitignore> {
+    let mut builder = GitignoreBuilder::new(root_dir);
+    builder.add_line(None, pattern).or(Err(LinterError::InvalidDependencyRule(pattern.clone())))?;
+    builder.build().or(Err(LinterError::InvalidDependencyRule(pattern.clone())))
+}
+
+// virtual entries are not in the file system, so they match no pattern
+fn is_matched(matcher: &Gitignore, entry: &Rc<RefCell<WisphaEntry>>, root_dir: &PathBuf) -> bool {
+    let properties = &entry.borrow().properties;
+    let path = &properties.absolute_path;
+    if properties.is_virtual() || !path.starts_with(root_dir) || path == root_dir {
+        return false;
+    }
+    matcher.matched_path_or_any_parents(path, path.is_dir()).is_ignore()
+}
+
+// direct dependencies forbidden by rules, reported with both ends and the rule
+pub fn check_dependency_rules(entry: &Rc<RefCell<WisphaEntry>>, rules: &Vec<DependencyRule>, root_dir: &PathBuf, issues: &mut Vec<LintIssue>) {
+    for rule in rules {
+        if !is_matched(&rule.from, entry, root_dir) {
+            continue;
+        }
+        for dependency in &*entry.borrow().dependencies.borrow() {
+            let dependency = dependency.upgrade().unwrap();
+            for (pattern, forbidden) in &rule.forbidden {
+                if is_matched(forbidden, &dependency, root_dir) {
+                    let message = format!("Dependency {} -> {} breaks the rule that {} may not depend on {}.",
+                                          path_of_entry(entry).to_str().unwrap(),
+                                          path_of_entry(&dependency).to_str().unwrap(),
+                                          rule.from_pattern,
+                                          pattern);
+                    issues.push(issue_of_resolved_dependency(entry, &dependency, message));
+                }
+            }
+        }
+    }
+    for sub_entry in &*entry.borrow().sub_entries.borrow() {
+        check_dependency_rules(sub_entry, rules, root_dir, issues);
+    }
+}
+
+// each cycle is reported once, at the entry closing it, with all entries on it
+pub fn check_cycles(root: &Rc<RefCell<WisphaEntry>>, issues: &mut Vec<LintIssue>) {
+    let mut entries = vec![];
+    push_into_entries(root, &mut entries);
+    let mut finished = HashSet::new();
+    let mut path = vec![];
+    for entry in &entries {
+        visit_dependencies(entry, &mut path, &mut finished, issues);
+    }
+}
+
+fn push_into_entries(entry: &Rc<RefCell<WisphaEntry>>, entries: &mut Vec<Rc<RefCell<WisphaEntry>>>) {
+    entries.push(Rc::clone(entry));
+    for sub_entry in &*entry.borrow().sub_entries.borrow() {
+        push_into_entries(sub_entry, entries);
+    }
+}
+
+// depth-first search, where `path` is the chain of dependencies leading to `entry`
+fn visit_dependencies(entry: &Rc<RefCell<WisphaEntry>>,
+                      path: &mut Vec<Rc<RefCell<WisphaEntry>>>,
+                      finished: &mut HashSet<*const RefCell<WisphaEntry>>,
+                      issues: &mut Vec<LintIssue>) {
+    if finished.contains(&Rc::as_ptr(entry)) {
+        return;
+    }
+    if let Some(position) = path.iter().position(|visited| Rc::ptr_eq(visited, entry)) {
+        let cycle: Vec<String> = path[position..].iter().chain(std::iter::once(entry))
+            .map(|entry| path_of_entry(entry).to_str().unwrap().to_string())
+            .collect();
+        issues.push(issue_of_resolved_dependency(path.last().unwrap(), entry, format!("Dependency cycle {}.", cycle.join(" -> "))));
+        return;
+    }
+    path.push(Rc::clone(entry));
+    let dependencies: Vec<Rc<RefCell<WisphaEntry>>> = entry.borrow().dependencies.borrow().iter()
+        .filter_map(|dependency| dependency.upgrade())
+        .collect();
+    for dependency in &dependencies {
+        visit_dependencies(dependency, path, finished, issues);
+    }
+    path.pop();
+    finished.insert(Rc::as_ptr(entry));
+}
@@ -0,0 +1,38 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter, Debug};
+
+use crate::config_reader::error::ConfigError;
+use crate::parser::error::ParserError;
+use crate::parser::option::ParserOptionError;
+
+#[derive(Debug)]
+pub enum LinterError {
+    ConfigError(ConfigError),
+    ParserError(ParserError),
+    ParserOptionError(ParserOptionError),
+    InvalidDependencyRule(String),
+}
+
+impl Error for LinterError { }
+
+impl Display for LinterError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        use LinterError::*;
+        let message = match &self {
+            ConfigError(error) => {
+                format!("{}", error)
+            },
+            ParserError(error) => {
+                format!("{}", error)
+            },
+            ParserOptionError(error) => {
+                format!("{}", error)
+            },
+            InvalidDependencyRule(pattern) => {
+                format!("Pattern {} in dependency rules is invalid.", pattern)
+            },
+        };
+        write!(f, "{}", message)
+    }
+}
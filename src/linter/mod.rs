@@ -0,0 +1,213 @@
+pub mod option;
+use option::*;
+
+pub mod error;
+use error::*;
+
+mod architecture;
+mod anchor;
+
+use crate::parser::{self, option::ParserOptions, external::ExternalProjects};
+use crate::config_reader::{self, EntryTypeConfig, property_schemas, inherited_property_names, schema::PropertySchema};
+use crate::wispha::common::*;
+use crate::strings::*;
+
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+
+type Result<T> = std::result::Result<T, LinterError>;
+
+// a problem found in `.wispha` files
+pub struct LintIssue {
+    pub file_path: PathBuf,
+    pub line_number: usize,
+    pub message: String,
+}
+
+impl Display for LintIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.file_path.to_str().unwrap(), self.line_number, self.message)
+    }
+}
+
+// state of the whole tree shared by all checks
+struct LintContext {
+    schemas: HashMap<String, PropertySchema>,
+    inherited_properties: Vec<String>,
+    entry_types: Vec<EntryTypeConfig>,
+    file_lines: anchor::FileLines,
+    externals: ExternalProjects,
+}
+
+// `path` is the path of the root `.wispha` file
+pub fn lint(path: &PathBuf, options: LinterOptions) -> Result<Vec<LintIssue>> {
+    let root_dir = path.parent().unwrap().to_path_buf();
+    let mut parser_options = ParserOptions::default();
+    let config = config_reader::read_configs_in_dir(&root_dir).or_else(|error| Err(LinterError::ConfigError(error)))?;
+    if let Some(config) = config {
+        parser_options.update_from_config(&config).or_else(|error| Err(LinterError::ParserOptionError(error)))?;
+    }
+    options.update_parser_options(&mut parser_options);
+    let externals = ExternalProjects::new(&parser_options.externals, &root_dir, options.threads);
+    let root = parser::parse(path, parser_options).or_else(|error| Err(LinterError::ParserError(error)))?;
+    let dependency_rules = architecture::compile_dependency_rules(&root_dir, &options.dependency_rules)?;
+
+    let context = LintContext {
+        schemas: property_schemas(&options.properties),
+        inherited_properties: inherited_property_names(&options.properties),
+        entry_types: options.entry_types.clone(),
+        file_lines: RefCell::new(HashMap::new()),
+        externals,
+    };
+    let mut issues = vec![];
+    lint_entry(&root, &context, &mut issues);
+    architecture::check_cycles(&root, &mut issues);
+    architecture::check_dependency_rules(&root, &dependency_rules, &root_dir, &mut issues);
+    Ok(issues)
+}
+
+fn lint_entry(entry: &Rc<RefCell<WisphaEntry>>, context: &LintContext, issues: &mut Vec<LintIssue>) {
+    check_property_values(entry, context, issues);
+    check_entry_type(entry, context, issues);
+    check_links(entry, issues);
+    check_unmatched_dependencies(entry, issues);
+    check_external_dependencies(entry, context, issues);
+    anchor::check_line_range(entry, &context.file_lines, issues);
+    for sub_entry in &*entry.borrow().sub_entries.borrow() {
+        lint_entry(sub_entry, context, issues);
+    }
+}
+
+// values conforming to their schemas, properties not repeated, and required properties present or inherited
+fn check_property_values(entry: &Rc<RefCell<WisphaEntry>>, context: &LintContext, issues: &mut Vec<LintIssue>) {
+    let borrowed_entry = entry.borrow();
+    let properties = &borrowed_entry.properties;
+    for name in &properties.repeated_properties {
+        issues.push(issue_of_property(entry, name, format!("Property {} is repeated. Only properties of list type can have several values, and the last one is used.", name)));
+    }
+    let mut names: Vec<&String> = context.schemas.keys().collect();
+    names.sort();
+    for name in names {
+        let schema = &context.schemas[name];
+        match properties.customized.get(name) {
+            Some(value) => {
+                if let Err(reason) = schema.validate(value) {
+                    issues.push(issue_of_property(entry, name, format!("Invalid value of property {}: {}.", name, reason)));
+                }
+            },
+            None => {
+                let is_inherited = context.inherited_properties.contains(name)
+                    && find_inherited_property(entry, name).is_some();
+                if schema.required && !is_inherited {
+                    issues.push(issue_of_property(entry, NAME_HEADER, format!("Entry {} lacks required property {}.", properties.name, name)));
+                }
+            },
+        }
+    }
+}
+
+// properties required by the type of the entry are present or inherited
+fn check_entry_type(entry: &Rc<RefCell<WisphaEntry>>, context: &LintContext, issues: &mut Vec<LintIssue>) {
+    let borrowed_entry = entry.borrow();
+    let properties = &borrowed_entry.properties;
+    let entry_type = match context.entry_types.iter().find(|entry_type| entry_type.name == properties.entry_type.to_str()) {
+        Some(entry_type) => entry_type,
+        None => return,
+    };
+    for name in entry_type.required_properties.as_ref().unwrap_or(&vec![]) {
+        let is_present = match name.as_str() {
+            DESCRIPTION_HEADER => properties.description.as_ref().map(|description| !description.trim().is_empty()).unwrap_or(false),
+            FINGERPRINT_HEADER => properties.fingerprint.is_some(),
+            ID_HEADER => properties.id.is_some(),
+            _ => {
+                properties.customized.contains_key(name)
+                    || (context.inherited_properties.contains(name) && find_inherited_property(entry, name).is_some())
+            },
+        };
+        if !is_present {
+            issues.push(issue_of_property(entry, ENTRY_TYPE_HEADER, format!("Entry {} of type {} lacks required property {}.", properties.name, entry_type.name, name)));
+        }
+    }
+}
+
+// links in description which cannot be resolved, reported at their own lines
+fn check_links(entry: &Rc<RefCell<WisphaEntry>>, issues: &mut Vec<LintIssue>) {
+    let borrowed_entry = entry.borrow();
+    let properties = &borrowed_entry.properties;
+    let description = match &properties.description {
+        Some(description) => description,
+        None => return,
+    };
+    let header_line = properties.property_lines.get(DESCRIPTION_HEADER).map(|(first, _)| *first).unwrap_or(1);
+    for (line_index, line) in description.lines().enumerate() {
+        for target in description_links(line) {
+            if !borrowed_entry.links.borrow().contains_key(&target) {
+                issues.push(LintIssue {
+                    file_path: properties.file_path.clone(),
+                    line_number: header_line + 1 + line_index,
+                    message: format!("Link to {} in description of entry {} cannot be resolved.", target, properties.name),
+                });
+            }
+        }
+    }
+}
+
+// references in `dependency` matching no entry
+fn check_unmatched_dependencies(entry: &Rc<RefCell<WisphaEntry>>, issues: &mut Vec<LintIssue>) {
+    let name = entry.borrow().properties.name.clone();
+    for reference in &*entry.borrow().unmatched_dependencies.borrow() {
+        let message = format!("Dependency {} of entry {} matches no entry.", reference_with_root_dir(reference).to_str().unwrap(), name);
+        issues.push(issue_of_dependency(entry, reference, message));
+    }
+}
+
+// dependencies in other projects, which are parsed when first referred to
+fn check_external_dependencies(entry: &Rc<RefCell<WisphaEntry>>, context: &LintContext, issues: &mut Vec<LintIssue>) {
+    let name = entry.borrow().properties.name.clone();
+    for reference in &*entry.borrow().external_dependencies.borrow() {
+        if let Err(error) = context.externals.resolve(reference) {
+            let message = format!("Dependency {} of entry {} cannot be resolved. {}", reference.to_str().unwrap(), name, error);
+            issues.push(issue_of_dependency(entry, reference, message));
+        }
+    }
+}
+
+// the issue is located at property `name` of `entry`, or at its first property if it has no such property
+fn issue_of_property(entry: &Rc<RefCell<WisphaEntry>>, name: &str, message: String) -> LintIssue {
+    let properties = &entry.borrow().properties;
+    let line_number = properties.property_lines.get(name)
+        .or(properties.property_lines.values().min())
+        .map(|(first, _)| *first)
+        .unwrap_or(1);
+    LintIssue {
+        file_path: properties.file_path.clone(),
+        line_number,
+        message,
+    }
+}
+
+// the issue is located at the `dependency` property giving `reference`
+fn issue_of_dependency(entry: &Rc<RefCell<WisphaEntry>>, reference: &PathBuf, message: String) -> LintIssue {
+    let mut issue = issue_of_property(entry, DEPENDENCY_HEADER, message);
+    if let Some(line_number) = entry.borrow().properties.dependency_lines.get(reference) {
+        issue.line_number = *line_number;
+    }
+    issue
+}
+
+// the issue is located at the `dependency` property from which `dependency` of `entry` is resolved
+fn issue_of_resolved_dependency(entry: &Rc<RefCell<WisphaEntry>>, dependency: &Rc<RefCell<WisphaEntry>>, message: String) -> LintIssue {
+    let reference = {
+        let borrowed_entry = entry.borrow();
+        let position = borrowed_entry.dependencies.borrow().iter()
+            .position(|existing| existing.upgrade().map(|existing| Rc::ptr_eq(&existing, dependency)).unwrap_or(false));
+        position.and_then(|position| borrowed_entry.dependency_references.borrow().get(position).cloned())
+    };
+    match reference {
+        Some(reference) => issue_of_dependency(entry, &reference, message),
+        None => issue_of_property(entry, DEPENDENCY_HEADER, message),
+    }
+}
@@ -0,0 +1,46 @@
+use crate::config_reader::{Config, PropertyConfig, EntryTypeConfig, DependencyRuleConfig};
+use crate::commandline::Lint;
+use crate::strings::*;
+use crate::parser::option::ParserOptions;
+
+pub struct LinterOptions {
+    pub properties: Vec<PropertyConfig>,
+    pub entry_types: Vec<EntryTypeConfig>,
+    pub dependency_rules: Vec<DependencyRuleConfig>,
+    pub threads: usize,
+}
+
+impl LinterOptions {
+    pub fn default() -> LinterOptions {
+        LinterOptions {
+            properties: vec![],
+            entry_types: vec![],
+            dependency_rules: vec![],
+            threads: DEFAULT_THREADS,
+        }
+    }
+
+    pub fn update_from_config(&mut self, config: &Config) {
+        if let Some(properties) = &config.properties {
+            self.properties = properties.clone();
+        }
+        if let Some(entry_types) = &config.entry_types {
+            self.entry_types = entry_types.clone();
+        }
+        if let Some(dependency_rules) = &config.dependency_rules {
+            self.dependency_rules = dependency_rules.clone();
+        }
+    }
+
+    pub fn update_from_commandline(&mut self, lint: &Lint) {
+        if let Some(threads) = lint.threads {
+            self.threads = threads;
+        }
+    }
+
+    // invalid values are collected by the linter instead of stopping the parser
+    pub fn update_parser_options(&self, parser_options: &mut ParserOptions) {
+        parser_options.check_values = false;
+        parser_options.threads = self.threads;
+    }
+}
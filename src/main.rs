@@ -8,11 +8,15 @@ mod stator;
 mod helper;
 mod strings;
 mod serializer;
+mod auditor;
+mod annotator;
+mod linter;
+mod analyzer;
 
 use crate::commandline::{WisphaCommand, Subcommand};
 use crate::generator::{error::GeneratorError, option::*};
-use crate::parser::{error::ParserError, option::*};
-use crate::manipulator::Manipulator;
+use crate::parser::{error::ParserError, option::*, external::ExternalProjects};
+use crate::manipulator::{Manipulator, error::ManipulatorError};
 use crate::config_reader::error::ConfigError;
 use crate::serializer::{error::SerializerError, options::*};
 
@@ -25,8 +29,20 @@ use std::error::Error;
 use std::fmt::{Display, Formatter};
 use std::result::Result;
 use std::fs;
+use std::process;
 use crate::stator::option::StatorOptions;
 use crate::stator::error::StatorError;
+use crate::auditor::option::{StaleOptions, FingerprintOptions};
+use crate::auditor::error::AuditorError;
+use crate::annotator::option::AnnotatorOptions;
+use crate::annotator::error::AnnotatorError;
+use crate::config_reader::AnnotationPreference;
+use crate::linter::option::LinterOptions;
+use crate::linter::error::LinterError;
+use crate::analyzer::option::AnalyzerOptions;
+use crate::analyzer::error::AnalyzerError;
+use crate::wispha::common::TagFilter;
+use crate::strings::ID_REFERENCE_PREFIX;
 
 // `raw`: relative or absolute. If cannot determine current directory, an error is raised
 fn actual_path(raw: &PathBuf) -> Result<PathBuf, MainError> {
@@ -71,14 +87,17 @@ fn main_with_error() -> Result<(), MainError> {
             // get parser options from config
             let mut options = ParserOptions::default();
             let config = config_reader::read_configs_in_dir(&actual_path.parent().unwrap().to_path_buf())?;
-            if let Some(config) = config {
-                options.update_from_config(&config)?;
+            if let Some(config) = &config {
+                options.update_from_config(config)?;
             }
             options.update_from_commandline(look);
+            let properties = options.properties.clone();
+            let externals = ExternalProjects::new(&options.externals, &actual_path.parent().unwrap().to_path_buf(), options.threads);
 
             let root = parser::parse(&actual_path, options)?;
 
-            let manipulator = Manipulator::new(&root, &root);
+            let entry_types = config.and_then(|config| config.entry_types).unwrap_or(vec![]);
+            let manipulator = Manipulator::new(&root, &root, &properties, &entry_types, externals);
             println!("Looking ready!");
             commandline::continue_program(manipulator);
         },
@@ -89,19 +108,28 @@ fn main_with_error() -> Result<(), MainError> {
             println!("Traversing to determine state...");
 
             let mut options = StatorOptions::default();
-            let config = config_reader::read_configs_in_dir(&actual_path)?;
+            let config = config_reader::read_configs_in_dir(&actual_path.parent().unwrap().to_path_buf())?;
             if let Some(config) = config {
                 options.update_from_config(&config);
             }
             options.update_from_commandline(state);
+            let annotation_preference = options.annotation_preference;
 
-            let unrecorded_files = stator::state_from_path(&actual_path, options)?;
-            if unrecorded_files.is_empty() {
+            let report = stator::state_from_path(&actual_path, options)?;
+            if report.unrecorded_paths.is_empty() {
                 println!("All valid files are recorded in Wispha.");
             } else {
-                let unrecorded_files_strs: Vec<String> = unrecorded_files.iter().map(|path| path.to_str().unwrap().to_string()).collect();
+                let unrecorded_files_strs: Vec<String> = report.unrecorded_paths.iter().map(|path| path.to_str().unwrap().to_string()).collect();
                 println!("The following file(s) are not recorded in Wispha:\n{}", unrecorded_files_strs.join("\n"));
             }
+            if !report.conflicting_paths.is_empty() {
+                let winner = match annotation_preference {
+                    AnnotationPreference::Source => "annotations are used",
+                    AnnotationPreference::Wispha => "Wispha is used",
+                };
+                let conflicting_files_strs: Vec<String> = report.conflicting_paths.iter().map(|path| path.to_str().unwrap().to_string()).collect();
+                println!("The annotations of following file(s) conflict with Wispha, and {}:\n{}", winner, conflicting_files_strs.join("\n"));
+            }
         },
 
         Subcommand::Convert(convert) => {
@@ -111,9 +139,16 @@ fn main_with_error() -> Result<(), MainError> {
             println!("Converting...");
 
             let mut parser_options = ParserOptions::default();
+            let config = config_reader::read_configs_in_dir(&actual_input.parent().unwrap().to_path_buf())?;
+            if let Some(config) = &config {
+                parser_options.update_from_config(config)?;
+            }
             convert.update_parser_options(&mut parser_options);
 
             let mut convert_options = SerializerOptions::default();
+            if let Some(config) = &config {
+                convert_options.update_from_config(config);
+            }
             convert_options.update_from_commandline(&convert)?;
 
             let output = if let Some(output) = &convert.output {
@@ -126,6 +161,246 @@ fn main_with_error() -> Result<(), MainError> {
             let root = parser::parse(&actual_input, parser_options)?;
             let converted = serializer::serialize(root, convert_options)?;
             fs::write(&output, converted).or(Err(MainError::PathCannotWrite(output.clone())))?;
+        },
+
+        Subcommand::Query(query) => {
+            let path = &query.path;
+            let actual_input = actual_path(&path)?;
+
+            let mut options = ParserOptions::default();
+            let config = config_reader::read_configs_in_dir(&actual_input.parent().unwrap().to_path_buf())?;
+            if let Some(config) = &config {
+                options.update_from_config(config)?;
+            }
+            query.update_parser_options(&mut options);
+            let properties = options.properties.clone();
+            let externals = ExternalProjects::new(&options.externals, &actual_input.parent().unwrap().to_path_buf(), options.threads);
+
+            let root = parser::parse(&actual_input, options)?;
+            let entry_types = config.and_then(|config| config.entry_types).unwrap_or(vec![]);
+            let manipulator = Manipulator::new(&root, &root, &properties, &entry_types, externals);
+            let filters: Vec<TagFilter> = query.tag.iter().map(|tag| TagFilter::from(tag)).collect();
+            let paths = manipulator.query(&filters);
+            if paths.is_empty() {
+                println!("No entry is found.");
+            } else {
+                let paths_strs: Vec<String> = paths.iter().map(|path| path.to_str().unwrap().to_string()).collect();
+                println!("{}", paths_strs.join("\n"));
+            }
+        },
+
+        Subcommand::Impact(impact) => {
+            let path = &impact.path;
+            let actual_input = actual_path(&path)?;
+
+            let mut options = ParserOptions::default();
+            let config = config_reader::read_configs_in_dir(&actual_input.parent().unwrap().to_path_buf())?;
+            if let Some(config) = &config {
+                options.update_from_config(config)?;
+            }
+            impact.update_parser_options(&mut options);
+            let properties = options.properties.clone();
+            let externals = ExternalProjects::new(&options.externals, &actual_input.parent().unwrap().to_path_buf(), options.threads);
+
+            let root = parser::parse(&actual_input, options)?;
+            let entry_types = config.and_then(|config| config.entry_types).unwrap_or(vec![]);
+            let manipulator = Manipulator::new(&root, &root, &properties, &entry_types, externals);
+            for entry in &impact.entries {
+                // `id:xyz` is not a path in the current directory
+                let entry_path = if entry.to_str().map(|entry| entry.starts_with(ID_REFERENCE_PREFIX)).unwrap_or(false) {
+                    entry.clone()
+                } else {
+                    actual_path(entry)?
+                };
+                let affected = manipulator.impact_of_file(&entry_path, impact.depth)?;
+                if affected.is_empty() {
+                    println!("No entry depends on {}.", entry.to_str().unwrap());
+                } else {
+                    println!("Entries depending on {}:", entry.to_str().unwrap());
+                    for (affected_path, depth) in affected {
+                        println!("{} ({})", affected_path.to_str().unwrap(), depth);
+                    }
+                }
+            }
+        },
+
+        Subcommand::Infer(infer) => {
+            let path = &infer.path;
+            let actual_input = actual_path(&path)?;
+            println!("Analyzing dependencies...");
+
+            let mut options = AnalyzerOptions::default();
+            let config = config_reader::read_configs_in_dir(&actual_input.parent().unwrap().to_path_buf())?;
+            if let Some(config) = config {
+                options.update_from_config(&config);
+            }
+            options.update_from_commandline(infer);
+
+            let sources = analyzer::infer(&actual_input, options)?;
+            if infer.check {
+                let mut problems = 0;
+                for source in &sources {
+                    let location = format!("{}:{}", source.file_path.to_str().unwrap(), source.line_number);
+                    for dependency in &source.missing {
+                        println!("{}: Dependency {} -> {} is found in code but not declared.", location, source.path.to_str().unwrap(), dependency.path.to_str().unwrap());
+                    }
+                    for dependency in &source.extra {
+                        println!("{}: Dependency {} -> {} is declared but not found in code.", location, source.path.to_str().unwrap(), dependency.to_str().unwrap());
+                    }
+                    for reference in &source.unresolved {
+                        println!("{}: Reference `{}` in {} cannot be resolved.", location, reference, source.path.to_str().unwrap());
+                    }
+                    problems += source.missing.len() + source.extra.len() + source.unresolved.len();
+                }
+                if problems == 0 {
+                    println!("Declared dependencies agree with the code.");
+                } else {
+                    return Err(MainError::ProblemsFound(problems));
+                }
+            } else {
+                for source in &sources {
+                    for reference in &source.unresolved {
+                        println!("Reference `{}` in {} cannot be resolved.", reference, source.path.to_str().unwrap());
+                    }
+                }
+                if infer.write {
+                    let count = analyzer::write_dependencies(&sources)?;
+                    println!("{} dependency(ies) added.", count);
+                } else if sources.iter().all(|source| source.missing.is_empty()) {
+                    println!("No undeclared dependency is found.");
+                } else {
+                    println!("The following dependencies are found in code but not declared:");
+                    for source in &sources {
+                        for dependency in &source.missing {
+                            println!("{} -> {}", source.path.to_str().unwrap(), dependency.path.to_str().unwrap());
+                        }
+                    }
+                }
+            }
+        },
+
+        Subcommand::Stale(stale) => {
+            let path = &stale.path;
+            let actual_input = actual_path(&path)?;
+            println!("Checking staleness...");
+
+            let mut options = StaleOptions::default();
+            let config = config_reader::read_configs_in_dir(&actual_input.parent().unwrap().to_path_buf())?;
+            if let Some(config) = config {
+                options.update_from_config(&config);
+            }
+            options.update_from_commandline(stale);
+
+            let stale_entries = auditor::stale_entries_from_path(&actual_input, options)?;
+            let report = serde_json::to_string_pretty(&stale_entries).or(Err(MainError::Unexpected))?;
+            if let Some(output) = &stale.output {
+                let output = actual_path(output)?;
+                fs::write(&output, report).or(Err(MainError::PathCannotWrite(output.clone())))?;
+            } else {
+                println!("{}", report);
+            }
+        },
+
+        Subcommand::Verify(verify) => {
+            let path = &verify.path;
+            let actual_input = actual_path(&path)?;
+            println!("Verifying fingerprints...");
+
+            let mut options = FingerprintOptions::default();
+            let config = config_reader::read_configs_in_dir(&actual_input.parent().unwrap().to_path_buf())?;
+            if let Some(config) = config {
+                options.update_from_config(&config);
+            }
+            options.update_from_verify(verify);
+
+            let changed_entries = auditor::changed_entries_from_path(&actual_input, options)?;
+            if changed_entries.is_empty() {
+                println!("All fingerprinted entries are unchanged since their descriptions were confirmed.");
+            } else {
+                let changed_entries_strs: Vec<String> = changed_entries.iter().map(|entry| {
+                    let path_str = entry.absolute_path.to_str().unwrap().to_string();
+                    if entry.exists { path_str } else { format!("{} (missing)", path_str) }
+                }).collect();
+                println!("The following entries have changed since their descriptions were confirmed:\n{}", changed_entries_strs.join("\n"));
+            }
+        },
+
+        Subcommand::Ack(ack) => {
+            let path = &ack.path;
+            let actual_input = actual_path(&path)?;
+
+            let mut options = FingerprintOptions::default();
+            let config = config_reader::read_configs_in_dir(&actual_input.parent().unwrap().to_path_buf())?;
+            if let Some(config) = config {
+                options.update_from_config(&config);
+            }
+            options.update_from_ack(ack);
+
+            let mut entries = vec![];
+            for entry in &ack.entries {
+                entries.push(actual_path(entry)?);
+            }
+            auditor::acknowledge(&actual_input, &entries, options)?;
+            println!("Fingerprints updated.");
+        },
+
+        Subcommand::Restructure(restructure) => {
+            let path = &restructure.path;
+            let actual_input = actual_path(&path)?;
+            println!("Restructuring...");
+
+            let mut options = GeneratorOptions::default();
+            let config = config_reader::read_configs_in_dir(&actual_input.parent().unwrap().to_path_buf())?;
+            if let Some(config) = config {
+                options.update_from_config(&config)?;
+            }
+            options.update_from_restructure(restructure)?;
+
+            generator::restructure(actual_input, options)?;
+            println!("Successfully restructure!");
+        },
+
+        Subcommand::Annotate(annotate) => {
+            let path = &annotate.path;
+            let actual_input = actual_path(&path)?;
+
+            let mut options = AnnotatorOptions::default();
+            options.update_from_commandline(annotate);
+
+            let mut entries = vec![];
+            for entry in &annotate.entries {
+                entries.push(actual_path(entry)?);
+            }
+            let annotated_files = annotator::annotate(&actual_input, &entries, options)?;
+            if annotated_files.is_empty() {
+                println!("No file is annotated.");
+            } else {
+                let annotated_files_strs: Vec<String> = annotated_files.iter().map(|path| path.to_str().unwrap().to_string()).collect();
+                println!("The following file(s) are annotated:\n{}", annotated_files_strs.join("\n"));
+            }
+        },
+
+        Subcommand::Lint(lint) => {
+            let path = &lint.path;
+            let actual_input = actual_path(&path)?;
+            println!("Linting...");
+
+            let mut options = LinterOptions::default();
+            let config = config_reader::read_configs_in_dir(&actual_input.parent().unwrap().to_path_buf())?;
+            if let Some(config) = config {
+                options.update_from_config(&config);
+            }
+            options.update_from_commandline(lint);
+
+            let issues = linter::lint(&actual_input, options)?;
+            if issues.is_empty() {
+                println!("No problem is found.");
+            } else {
+                for issue in &issues {
+                    println!("{}", issue);
+                }
+                return Err(MainError::ProblemsFound(issues.len()));
+            }
         }
     }
     Ok(())
@@ -136,6 +411,7 @@ fn main() {
     if let Err(error) = result {
         eprintln!("{}", style("error").red());
         eprintln!("{}", error);
+        process::exit(1);
     }
 }
 
@@ -151,6 +427,13 @@ pub enum MainError {
     StatorError(StatorError),
     SerializerError(SerializerError),
     SerializerOptionError(SerializerOptionError),
+    AuditorError(AuditorError),
+    AnnotatorError(AnnotatorError),
+    LinterError(LinterError),
+    ManipulatorError(ManipulatorError),
+    AnalyzerError(AnalyzerError),
+    ProblemsFound(usize),
+    Unexpected,
 }
 
 impl Error for MainError { }
@@ -189,6 +472,27 @@ impl Display for MainError {
             SerializerOptionError(error) => {
                 format!("{}", error)
             },
+            AuditorError(error) => {
+                format!("{}", error)
+            },
+            AnnotatorError(error) => {
+                format!("{}", error)
+            },
+            LinterError(error) => {
+                format!("{}", error)
+            },
+            ManipulatorError(error) => {
+                format!("{}", error)
+            },
+            AnalyzerError(error) => {
+                format!("{}", error)
+            },
+            ProblemsFound(count) => {
+                format!("{} problem(s) found.", count)
+            },
+            Unexpected => {
+                format!("Unexpected error. Please retry.")
+            },
         };
         write!(f, "{}", error_message)
     }
@@ -241,3 +545,32 @@ impl From<SerializerOptionError> for MainError {
         MainError::SerializerOptionError(error)
     }
 }
+
+impl From<AuditorError> for MainError {
+    fn from(error: AuditorError) -> Self {
+        MainError::AuditorError(error)
+    }
+}
+
+impl From<AnnotatorError> for MainError {
+    fn from(error: AnnotatorError) -> Self {
+        MainError::AnnotatorError(error)
+    }
+}
+impl From<LinterError> for MainError {
+    fn from(error: LinterError) -> Self {
+        MainError::LinterError(error)
+    }
+}
+
+impl From<ManipulatorError> for MainError {
+    fn from(error: ManipulatorError) -> Self {
+        MainError::ManipulatorError(error)
+    }
+}
+
+impl From<AnalyzerError> for MainError {
+    fn from(error: AnalyzerError) -> Self {
+        MainError::AnalyzerError(error)
+    }
+}
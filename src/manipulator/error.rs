@@ -3,6 +3,8 @@ use std::fmt;
 use std::fmt::{Display, Formatter, Debug};
 use std::path::PathBuf;
 
+use crate::parser::error::ParserError;
+
 #[derive(Debug)]
 pub enum ManipulatorError {
     PathNotEntry(PathBuf),
@@ -11,6 +13,10 @@ pub enum ManipulatorError {
     BeyondDomain,
     EntryNotFound(PathBuf),
     PropertyNotFound,
+    IdNotFound(String),
+    NoLineRange(String),
+    FileCannotRead(PathBuf),
+    ParserError(ParserError),
     Unexpected,
 }
 
@@ -38,6 +44,18 @@ impl Display for ManipulatorError {
             PropertyNotFound => {
                 format!("Cannot find this property.")
             }
+            IdNotFound(id) => {
+                format!("Cannot find entry with id {}", id)
+            },
+            NoLineRange(name) => {
+                format!("Entry {} has no line range.", name)
+            },
+            FileCannotRead(path) => {
+                format!("Cannot read file {}.", path.to_str().unwrap())
+            },
+            ParserError(error) => {
+                format!("{}", error)
+            },
             Unexpected => {
                 format!("Unexpected error.")
             },
@@ -1,10 +1,16 @@
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use std::path::{PathBuf, Component};
 use std::env;
+use std::fs;
+
+use console::style;
 
 use crate::wispha::common::*;
+use crate::wispha::core::line_range_string;
+use crate::parser::external::ExternalProjects;
+use crate::config_reader::{PropertyConfig, EntryTypeConfig, inherited_property_names, list_property_names, color_of};
 use crate::strings::*;
 
 pub mod error;
@@ -16,15 +22,29 @@ pub struct Manipulator {
     pub root: Rc<RefCell<WisphaEntry>>,
     pub current_entry: Rc<RefCell<WisphaEntry>>,
     pub entries: HashMap<PathBuf, Rc<RefCell<WisphaEntry>>>,
+    pub ids: HashMap<String, Rc<RefCell<WisphaEntry>>>,
+    pub inherited_properties: Vec<String>,
+    pub list_properties: Vec<String>,
+    pub entry_types: Vec<EntryTypeConfig>,
+    pub externals: ExternalProjects,
 }
 
 impl Manipulator {
-    pub fn new(root: &Rc<RefCell<WisphaEntry>>, current_entry: &Rc<RefCell<WisphaEntry>>) -> Manipulator {
+    pub fn new(root: &Rc<RefCell<WisphaEntry>>,
+               current_entry: &Rc<RefCell<WisphaEntry>>,
+               properties: &Vec<PropertyConfig>,
+               entry_types: &Vec<EntryTypeConfig>,
+               externals: ExternalProjects) -> Manipulator {
         let root = Rc::clone(root);
         let current_entry = Rc::clone(current_entry);
         let mut entries: HashMap<PathBuf, Rc<RefCell<WisphaEntry>>> = HashMap::new();
         push_into_entries(&root, &mut entries);
-        Manipulator { root, current_entry, entries }
+        let mut ids: HashMap<String, Rc<RefCell<WisphaEntry>>> = HashMap::new();
+        push_into_ids(&root, &mut ids);
+        let inherited_properties = inherited_property_names(properties);
+        let list_properties = list_property_names(properties);
+        let entry_types = entry_types.clone();
+        Manipulator { root, current_entry, entries, ids, inherited_properties, list_properties, entry_types, externals }
     }
 
     pub fn set_current_entry_to_local_path(&mut self, path: &PathBuf) -> Result<()> {
@@ -44,7 +64,11 @@ impl Manipulator {
             return Err(ManipulatorError::AbsolutePathNotSupported);
         }
 
-        self.current_entry = if path.starts_with(ROOT_DIR) {
+        self.current_entry = if let Some(id) = id_of_reference(path) {
+            self.entry_of_id(id)?
+        } else if self.externals.is_external(path) {
+            self.externals.resolve(path).or_else(|error| Err(ManipulatorError::ParserError(error)))?
+        } else if path.starts_with(ROOT_DIR) {
             let remain_path = path.strip_prefix(ROOT_DIR).unwrap().to_path_buf();
             let used_path = PathBuf::from(ROOT_DIR);
             find_entry(Rc::clone(&self.root), remain_path, used_path)?
@@ -54,31 +78,41 @@ impl Manipulator {
         Ok(())
     }
 
+    // broken links are shown in red, and links to ids are followed by the paths of their targets
+    fn highlight_links(&self, description: &str) -> String {
+        let links = self.current_entry.borrow().links.borrow().clone();
+        replace_links(description, |target| {
+            let link = format!("[[{}]]", target);
+            match links.get(target).and_then(|linked| linked.upgrade()) {
+                Some(linked) if id_of_reference(&PathBuf::from(target)).is_some() => {
+                    format!("{} {}", style(link).cyan().underlined(), style(format!("({})", self.path_of(&linked).to_str().unwrap())).dim())
+                },
+                Some(_) => style(link).cyan().underlined().to_string(),
+                None => style(link).red().to_string(),
+            }
+        })
+    }
+
+    fn entry_of_id(&self, id: &str) -> Result<Rc<RefCell<WisphaEntry>>> {
+        self.ids.get(id)
+            .map(|entry| Rc::clone(entry))
+            .ok_or(ManipulatorError::IdNotFound(id.to_string()))
+    }
+
     pub fn current_path(&self) -> PathBuf {
-        let raw = (*self.current_entry)
-            .borrow()
-            .properties
-            .absolute_path.clone();
-        let root_dir = PathBuf::from(env::var(ROOT_DIR_VAR).unwrap());
-        if raw.starts_with(&root_dir) {
-            PathBuf::from(ROOT_DIR).join(raw.strip_prefix(root_dir).unwrap().to_path_buf())
-        } else {
-            PathBuf::from(ROOT_DIR).join(raw)
-        }
+        self.path_of(&self.current_entry)
     }
 
-    pub fn current_list(&self) -> String {
-        let mut names: Vec<String> = Vec::new();
-        for sub_entry in &*(*self.current_entry).borrow().sub_entries.borrow() {
-            let sub_entry = Rc::clone(sub_entry);
-            let sub_entry = (*sub_entry).borrow();
-            names.push(sub_entry.properties.name.clone());
-        }
+    // entries in other projects are shown with the aliases of their projects
+    fn path_of(&self, entry: &Rc<RefCell<WisphaEntry>>) -> PathBuf {
+        self.externals.reference_of(entry).unwrap_or_else(|| path_of_entry(entry))
+    }
 
-        names.join("\n")
+    pub fn current_list(&self, filters: &Vec<TagFilter>) -> String {
+        self.list_of_entry(&self.current_entry, filters)
     }
 
-    pub fn list_of_local_path(&self, path: &PathBuf) -> Result<String> {
+    pub fn list_of_local_path(&self, path: &PathBuf, filters: &Vec<TagFilter>) -> Result<String> {
         let current_path = (*self.current_entry)
             .borrow()
             .properties
@@ -86,15 +120,7 @@ impl Manipulator {
         let actual_path = actual_path(path, &current_path)?;
         match self.entries.get(&actual_path) {
             Some(entry) => {
-                let mut names: Vec<String> = Vec::new();
-                let entry = Rc::clone(entry);
-                for sub_entry in &*(*entry).borrow().sub_entries.borrow() {
-                    let sub_entry = Rc::clone(sub_entry);
-                    let sub_entry = (*sub_entry).borrow();
-                    names.push(sub_entry.properties.name.clone());
-                }
-
-                Ok(names.join("\n"))
+                Ok(self.list_of_entry(entry, filters))
             },
 
             None => {
@@ -103,28 +129,140 @@ impl Manipulator {
         }
     }
 
-    pub fn list_of_path(&self, path: &PathBuf) -> Result<String> {
-        let entry = if path.starts_with(ROOT_DIR) {
+    pub fn list_of_path(&self, path: &PathBuf, filters: &Vec<TagFilter>) -> Result<String> {
+        let entry = self.entry_of_path(path)?;
+        Ok(self.list_of_entry(&entry, filters))
+    }
+
+    fn entry_of_path(&self, path: &PathBuf) -> Result<Rc<RefCell<WisphaEntry>>> {
+        if let Some(id) = id_of_reference(path) {
+            self.entry_of_id(id)
+        } else if self.externals.is_external(path) {
+            self.externals.resolve(path).or_else(|error| Err(ManipulatorError::ParserError(error)))
+        } else if path.starts_with(ROOT_DIR) {
             let remain_path = path.strip_prefix(ROOT_DIR).unwrap().to_path_buf();
             let used_path = PathBuf::from(ROOT_DIR);
-            find_entry(Rc::clone(&self.root), remain_path, used_path)?
+            find_entry(Rc::clone(&self.root), remain_path, used_path)
         } else {
-            find_entry(Rc::clone(&self.current_entry), path.clone(), PathBuf::new())?
+            find_entry(Rc::clone(&self.current_entry), path.clone(), PathBuf::new())
+        }
+    }
+
+    // entries depending on the entry at `path` (or the current entry), directly or through other entries
+    pub fn dependents_of_path(&self, path: &Option<PathBuf>, max_depth: Option<usize>) -> Result<String> {
+        let entry = match path {
+            Some(path) => self.entry_of_path(path)?,
+            None => Rc::clone(&self.current_entry),
         };
+        Ok(dependents_of(vec![entry], max_depth).iter()
+            .map(|(dependent, depth)| format!("{} ({})", path_of_entry(dependent).to_str().unwrap(), depth))
+            .collect::<Vec<String>>().join("\n"))
+    }
+
+    // lines in the line range of the entry at `path` (or the current entry), numbered. Lines beyond the file are omitted
+    pub fn lines_of_path(&self, path: &Option<PathBuf>) -> Result<String> {
+        let entry = match path {
+            Some(path) => self.entry_of_path(path)?,
+            None => Rc::clone(&self.current_entry),
+        };
+        let properties = &entry.borrow().properties;
+        let (first, last) = properties.line_range.ok_or(ManipulatorError::NoLineRange(properties.name.clone()))?;
+        let content = fs::read_to_string(&properties.absolute_path)
+            .or(Err(ManipulatorError::FileCannotRead(properties.absolute_path.clone())))?;
+        let width = last.to_string().len();
+        Ok(content.lines().enumerate()
+            .skip(first - 1)
+            .take(last - first + 1)
+            .map(|(index, line)| format!("{} {}", style(format!("{:>width$}", index + 1, width = width)).dim(), line))
+            .collect::<Vec<String>>().join("\n"))
+    }
+
+    // entries affected by changing the file at `path`, which is absolute or an id reference. Entries depending on
+    // directories containing the file, or on entries inside the file, are affected too
+    pub fn impact_of_file(&self, path: &PathBuf, max_depth: Option<usize>) -> Result<Vec<(PathBuf, usize)>> {
+        let entry = match id_of_reference(path) {
+            Some(id) => self.entry_of_id(id)?,
+            None => {
+                let target = path.canonicalize().or(Err(ManipulatorError::PathNotEntry(path.clone())))?;
+                find_entry_by_path(Rc::clone(&self.root), &target).ok_or(ManipulatorError::PathNotEntry(path.clone()))?
+            },
+        };
+        let mut changed = vec![];
+        push_into_subtree(&entry, &mut changed);
+        let mut sup_entry = entry.borrow().sup_entry.borrow().upgrade();
+        while let Some(current) = sup_entry {
+            sup_entry = current.borrow().sup_entry.borrow().upgrade();
+            changed.push(current);
+        }
+        Ok(dependents_of(changed, max_depth).iter()
+            .map(|(dependent, depth)| (path_of_entry(dependent), *depth))
+            .collect())
+    }
+
+    // names of sub entries of `entry` matching all `filters`, in colors of their types
+    fn list_of_entry(&self, entry: &Rc<RefCell<WisphaEntry>>, filters: &Vec<TagFilter>) -> String {
         let mut names: Vec<String> = Vec::new();
         for sub_entry in &*(*entry).borrow().sub_entries.borrow() {
-            let sub_entry = Rc::clone(sub_entry);
-            let sub_entry = (*sub_entry).borrow();
-            names.push(sub_entry.properties.name.clone());
+            if self.matches_filters(sub_entry, filters) {
+                let properties = &(*sub_entry).borrow().properties;
+                let color = self.entry_types.iter()
+                    .find(|entry_type| entry_type.name == properties.entry_type.to_str())
+                    .and_then(|entry_type| entry_type.color.as_ref())
+                    .and_then(|color| color_of(color));
+                match color {
+                    Some(color) => names.push(style(&properties.name).fg(color).to_string()),
+                    None => names.push(properties.name.clone()),
+                }
+            }
+        }
+        // members of virtual entries are shown by their paths
+        for member in &*(*entry).borrow().members.borrow() {
+            let member = member.upgrade().unwrap();
+            if self.matches_filters(&member, filters) {
+                names.push(self.path_of(&member).to_str().unwrap().to_owned());
+            }
+        }
+
+        names.join("\n")
+    }
+
+    // paths of all entries matching all `filters`
+    pub fn query(&self, filters: &Vec<TagFilter>) -> Vec<PathBuf> {
+        let mut paths = vec![];
+        self.push_matching_paths(&self.root, filters, &mut paths);
+        paths
+    }
+
+    fn push_matching_paths(&self, entry: &Rc<RefCell<WisphaEntry>>, filters: &Vec<TagFilter>, paths: &mut Vec<PathBuf>) {
+        if self.matches_filters(entry, filters) {
+            paths.push(path_of_entry(entry));
+        }
+        for sub_entry in &*(*entry).borrow().sub_entries.borrow() {
+            self.push_matching_paths(sub_entry, filters, paths);
         }
+    }
 
-        Ok(names.join("\n"))
+    fn matches_filters(&self, entry: &Rc<RefCell<WisphaEntry>>, filters: &Vec<TagFilter>) -> bool {
+        filters.iter().all(|filter| filter.matches(|property| self.value_of_property(entry, property), &self.list_properties))
+    }
+
+    // the value of customized property `property` of `entry`, which may be inherited
+    fn value_of_property(&self, entry: &Rc<RefCell<WisphaEntry>>, property: &str) -> Option<String> {
+        if let Some(value) = (*entry).borrow().properties.customized.get(property) {
+            return Some(value.clone());
+        }
+        if self.inherited_properties.iter().any(|name| name == property) {
+            return find_inherited_property(entry, property).map(|(value, _)| value);
+        }
+        None
     }
 
     pub fn info_of_property(&self, property: &String) -> Result<String> {
         match property.as_str() {
             ABSOLUTE_PATH_HEADER => {
-                return Ok(self.current_entry.borrow().properties.absolute_path.to_str().unwrap().to_string());
+                if !self.current_entry.borrow().properties.is_virtual() {
+                    return Ok(self.current_entry.borrow().properties.absolute_path.to_str().unwrap().to_string());
+                }
             },
             NAME_HEADER => {
                 return Ok(self.current_entry.borrow().properties.name.clone());
@@ -136,20 +274,56 @@ impl Manipulator {
             },
             DESCRIPTION_HEADER => {
                 if let Some(description) = &self.current_entry.borrow().properties.description {
-                    return Ok(description.clone());
+                    return Ok(self.highlight_links(description));
+                }
+            },
+            FINGERPRINT_HEADER => {
+                if let Some(fingerprint) = &self.current_entry.borrow().properties.fingerprint {
+                    return Ok(fingerprint.clone());
+                }
+            },
+            ID_HEADER => {
+                if let Some(id) = &self.current_entry.borrow().properties.id {
+                    return Ok(id.clone());
+                }
+            },
+            KIND_HEADER => {
+                if let Some(kind) = &self.current_entry.borrow().properties.kind {
+                    return Ok(kind.clone());
+                }
+            },
+            LINE_RANGE_HEADER => {
+                if let Some(line_range) = &self.current_entry.borrow().properties.line_range {
+                    return Ok(line_range_string(line_range));
                 }
             },
+            SYMBOL_HEADER => {
+                if let Some(symbol) = &self.current_entry.borrow().properties.symbol {
+                    return Ok(symbol.clone());
+                }
+            },
+            // dependencies in other projects are shown as written, and followed by `cd`
             DEPENDENCY_HEADER => {
                 return Ok(self.current_entry.borrow().dependencies.borrow().iter()
-                    .map(|dependency| dependency.upgrade().unwrap().borrow()
-                                                                    .properties
-                                                                    .absolute_path.to_str().unwrap().to_owned())
+                    .map(|dependency| self.path_of(&dependency.upgrade().unwrap()).to_str().unwrap().to_owned())
+                    .chain(self.current_entry.borrow().external_dependencies.borrow().iter()
+                        .map(|reference| reference.to_str().unwrap().to_owned()))
+                    .collect::<Vec<String>>().join("\n"));
+            },
+            MEMBER_HEADER => {
+                return Ok(self.current_entry.borrow().members.borrow().iter()
+                    .map(|member| self.path_of(&member.upgrade().unwrap()).to_str().unwrap().to_owned())
                     .collect::<Vec<String>>().join("\n"));
             },
             _ => {
                 if let Some(value) = self.current_entry.borrow().properties.customized.get(property) {
                     return Ok(value.clone());
                 }
+                if self.inherited_properties.contains(property) {
+                    if let Some((value, source)) = find_inherited_property(&self.current_entry, property) {
+                        return Ok(format!("{}\n(inherited from {})", value, self.path_of(&source).to_str().unwrap()));
+                    }
+                }
             }
         }
         Err(ManipulatorError::PropertyNotFound)
@@ -158,12 +332,54 @@ impl Manipulator {
 
 fn push_into_entries(entry: &Rc<RefCell<WisphaEntry>>, entries: &mut HashMap<PathBuf, Rc<RefCell<WisphaEntry>>>) {
     let entry = Rc::clone(entry);
-    entries.insert((*entry).borrow().properties.absolute_path.clone(), Rc::clone(&entry));
+    if (*entry).borrow().properties.has_own_path() {
+        entries.insert((*entry).borrow().properties.absolute_path.clone(), Rc::clone(&entry));
+    }
     for sub_entry in &*(*entry).borrow().sub_entries.borrow() {
         push_into_entries(sub_entry, entries);
     }
 }
 
+// entries depending on `changed` with the least number of steps, in the order they are reached.
+// Entries more than `max_depth` steps away are not included
+fn dependents_of(changed: Vec<Rc<RefCell<WisphaEntry>>>, max_depth: Option<usize>) -> Vec<(Rc<RefCell<WisphaEntry>>, usize)> {
+    let mut visited: HashSet<*const RefCell<WisphaEntry>> = changed.iter().map(|entry| Rc::as_ptr(entry)).collect();
+    let mut dependents = vec![];
+    let mut frontier = changed;
+    let mut depth = 0;
+    while !frontier.is_empty() && max_depth.map(|max_depth| depth < max_depth).unwrap_or(true) {
+        depth += 1;
+        let mut next_frontier = vec![];
+        for entry in &frontier {
+            for dependent in &*entry.borrow().dependents.borrow() {
+                let dependent = dependent.upgrade().unwrap();
+                if visited.insert(Rc::as_ptr(&dependent)) {
+                    dependents.push((Rc::clone(&dependent), depth));
+                    next_frontier.push(dependent);
+                }
+            }
+        }
+        frontier = next_frontier;
+    }
+    dependents
+}
+
+fn push_into_subtree(entry: &Rc<RefCell<WisphaEntry>>, subtree: &mut Vec<Rc<RefCell<WisphaEntry>>>) {
+    subtree.push(Rc::clone(entry));
+    for sub_entry in &*entry.borrow().sub_entries.borrow() {
+        push_into_subtree(sub_entry, subtree);
+    }
+}
+
+fn push_into_ids(entry: &Rc<RefCell<WisphaEntry>>, ids: &mut HashMap<String, Rc<RefCell<WisphaEntry>>>) {
+    if let Some(id) = &(*entry).borrow().properties.id {
+        ids.insert(id.clone(), Rc::clone(entry));
+    }
+    for sub_entry in &*(*entry).borrow().sub_entries.borrow() {
+        push_into_ids(sub_entry, ids);
+    }
+}
+
 fn actual_path(raw: &PathBuf, current_dir: &PathBuf) -> Result<PathBuf> {
     if raw.is_absolute() {
         return Ok(raw.clone());
@@ -12,9 +12,15 @@ pub enum ParserError {
     FileCannotRead(PathBuf),
     UnexpectedToken(WisphaToken, Option<Vec<(WisphaToken, Vec<WisphaExpectOption>)>>),
     EmptyBody(WisphaToken),
-    EnvNotFound,
     ThreadPoolError(ThreadPoolError),
-    DependencyNotFound(PathBuf),
+    MemberNotFound(PathBuf),
+    InvalidPropertyValue(WisphaToken, String, String),
+    DuplicateId(String, PathBuf, PathBuf),
+    InvalidLineRange(WisphaToken),
+    UnknownProjectAlias(String),
+    ExternalConfigError(String),
+    ExternalProjectError(String, Box<ParserError>),
+    ExternalEntryNotFound(PathBuf),
     Unexpected,
 }
 
@@ -45,14 +51,42 @@ impl Display for ParserError {
                              token.raw_token().line_number,
                              token.raw_token().content.clone())
             },
-            EnvNotFound => {
-                format!("Cannot determine the environment variable.")
-            },
             ThreadPoolError(error) => {
                 format!("{}", error)
             },
-            DependencyNotFound(path) => {
-                format!("Cannot find dependency at path {}", path.to_str().unwrap())
+            MemberNotFound(path) => {
+                format!("Cannot find member {}", path.to_str().unwrap())
+            },
+            InvalidPropertyValue(token, name, reason) => {
+                format!("In file {}, line {}:\nInvalid value of property {}: {}.",
+                             token.raw_token().file_path.to_str().unwrap(),
+                             token.raw_token().line_number,
+                             name,
+                             reason)
+            },
+            DuplicateId(id, first_file, second_file) => {
+                format!("Id {} is used by more than one entry, in {} and {}.",
+                        id,
+                        first_file.to_str().unwrap(),
+                        second_file.to_str().unwrap())
+            },
+            InvalidLineRange(token) => {
+                format!("In file {}, line {}:\nInvalid line range {}. Line ranges are written as `first-last`, starting at 1.",
+                             token.raw_token().file_path.to_str().unwrap(),
+                             token.raw_token().line_number,
+                             token.raw_token().content.trim())
+            },
+            UnknownProjectAlias(alias) => {
+                format!("Project alias {} is not declared in externals.", alias)
+            },
+            ExternalConfigError(alias) => {
+                format!("Cannot read the config of project {}.", alias)
+            },
+            ExternalProjectError(alias, error) => {
+                format!("In project {}:\n{}", alias, error)
+            },
+            ExternalEntryNotFound(path) => {
+                format!("Cannot find entry {}.", path.to_str().unwrap())
             },
             Unexpected => {
                 format!("Unexpected error. Please retry.")
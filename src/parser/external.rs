@@ -0,0 +1,125 @@
+use std::path::{PathBuf, Component};
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::parser::{self, option::ParserOptions, error::ParserError};
+use crate::config_reader;
+use crate::wispha::common::*;
+use crate::strings::*;
+
+type Result<T> = std::result::Result<T, ParserError>;
+
+// projects declared in `externals` of config. Each project is parsed with its own root directory and config
+// when an entry in it is first needed
+pub struct ExternalProjects {
+    paths: HashMap<String, PathBuf>, // alias and the absolute path of the root `.wispha` file
+    roots: RefCell<HashMap<String, Rc<RefCell<WisphaEntry>>>>, // projects parsed so far
+    threads: usize,
+}
+
+impl ExternalProjects {
+    pub fn new(externals: &HashMap<String, String>, root_dir: &PathBuf, threads: usize) -> ExternalProjects {
+        let paths = externals.iter()
+            .map(|(alias, path)| {
+                let path = root_dir.join(path);
+                (alias.clone(), path.canonicalize().unwrap_or(path))
+            })
+            .collect();
+        ExternalProjects {
+            paths,
+            roots: RefCell::new(HashMap::new()),
+            threads,
+        }
+    }
+
+    // the entry referred to by a reference like `corelib:$ROOT_DIR/src/x.rs` or `corelib:id:xyz`
+    pub fn resolve(&self, reference: &PathBuf) -> Result<Rc<RefCell<WisphaEntry>>> {
+        let (alias, inner_reference) = external_of_reference(reference, &self.paths).ok_or(ParserError::ExternalEntryNotFound(reference.clone()))?;
+        let root = self.root_of(&alias)?;
+        let root_dir = self.paths[&alias].parent().unwrap().to_path_buf();
+        let found = match id_of_reference(&inner_reference) {
+            Some(id) => find_entry_with_id(&root, id),
+            None => {
+                let relative_path = if inner_reference.starts_with(ROOT_DIR) {
+                    inner_reference.strip_prefix(ROOT_DIR).ok().map(|path| path.to_path_buf())
+                } else if inner_reference.is_absolute() {
+                    inner_reference.strip_prefix(&root_dir).ok().map(|path| path.to_path_buf())
+                } else {
+                    Some(inner_reference.clone())
+                };
+                relative_path.and_then(|relative_path| find_entry_at_relative_path(&root, &relative_path))
+            },
+        };
+        found.ok_or(ParserError::ExternalEntryNotFound(reference.clone()))
+    }
+
+    // whether `reference` refers to an entry in one of the projects
+    pub fn is_external(&self, reference: &PathBuf) -> bool {
+        external_of_reference(reference, &self.paths).is_some()
+    }
+
+    // the reference to `entry` if it is in a project parsed so far, such as `corelib:$ROOT_DIR/src/x.rs`
+    pub fn reference_of(&self, entry: &Rc<RefCell<WisphaEntry>>) -> Option<PathBuf> {
+        let mut current = Rc::clone(entry);
+        loop {
+            let sup_entry = current.borrow().sup_entry.borrow().upgrade();
+            match sup_entry {
+                Some(sup_entry) => current = sup_entry,
+                None => break,
+            }
+        }
+        let roots = self.roots.borrow();
+        let (alias, _) = roots.iter().find(|(_, root)| Rc::ptr_eq(root, &current))?;
+        let path = path_of_entry_in(entry, self.paths[alias].parent().unwrap());
+        Some(PathBuf::from(format!("{}:{}", alias, path.to_str().unwrap())))
+    }
+
+    fn root_of(&self, alias: &String) -> Result<Rc<RefCell<WisphaEntry>>> {
+        if let Some(root) = self.roots.borrow().get(alias) {
+            return Ok(Rc::clone(root));
+        }
+        let path = self.paths.get(alias).ok_or(ParserError::UnknownProjectAlias(alias.clone()))?;
+        let mut options = ParserOptions::default();
+        let config = config_reader::read_configs_in_dir(&path.parent().unwrap().to_path_buf())
+            .or(Err(ParserError::ExternalConfigError(alias.clone())))?;
+        if let Some(config) = &config {
+            options.update_from_config(config).or(Err(ParserError::ExternalConfigError(alias.clone())))?;
+        }
+        // values in other projects are not checked by this one
+        options.check_values = false;
+        options.threads = self.threads;
+
+        // `$ROOT_DIR` of this project is kept
+        let root = parser::parse_with_root_dir(path, path.parent().unwrap(), options).or_else(|error| Err(ParserError::ExternalProjectError(alias.clone(), Box::new(error))))?;
+        self.roots.borrow_mut().insert(alias.clone(), Rc::clone(&root));
+        Ok(root)
+    }
+}
+
+fn find_entry_with_id(entry: &Rc<RefCell<WisphaEntry>>, id: &str) -> Option<Rc<RefCell<WisphaEntry>>> {
+    if entry.borrow().properties.id.as_ref().map(|entry_id| entry_id == id).unwrap_or(false) {
+        return Some(Rc::clone(entry));
+    }
+    let result = entry.borrow().sub_entries.borrow().iter().find_map(|sub_entry| find_entry_with_id(sub_entry, id));
+    result
+}
+
+// entries are found by their names, so that program entries can be reached too
+fn find_entry_at_relative_path(root: &Rc<RefCell<WisphaEntry>>, relative_path: &PathBuf) -> Option<Rc<RefCell<WisphaEntry>>> {
+    let mut current = Rc::clone(root);
+    for component in relative_path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::Normal(name) => {
+                let name = name.to_str()?;
+                let sub_entry = current.borrow().sub_entries.borrow().iter()
+                    .find(|sub_entry| sub_entry.borrow().properties.name == name)
+                    .map(Rc::clone)?;
+                current = sub_entry;
+            },
+            _ => return None,
+        }
+    }
+    Some(current)
+}
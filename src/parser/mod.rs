@@ -1,4 +1,5 @@
 use onig::*;
+use ignore::gitignore::GitignoreBuilder;
 
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
@@ -20,6 +21,8 @@ pub mod option;
 
 use option::*;
 
+pub mod external;
+
 pub mod error;
 
 use error::ParserError;
@@ -28,30 +31,54 @@ use std::collections::HashMap;
 
 type Result<T> = std::result::Result<T, ParserError>;
 
+// `$ROOT_DIR` in the rest of the program refers to the directory of `file_path` afterwards
 pub fn parse(file_path: &Path, options: ParserOptions) -> Result<Rc<RefCell<WisphaEntry>>> {
+    let root_dir = file_path.parent().unwrap();
+    env::set_var(ROOT_DIR_VAR, root_dir.to_str().unwrap());
+    parse_with_root_dir(file_path, root_dir, options)
+}
+
+// `$ROOT_DIR` in the files refers to `root_dir`, and the environment is left untouched, so that other projects can be parsed
+pub fn parse_with_root_dir(file_path: &Path, root_dir: &Path, options: ParserOptions) -> Result<Rc<RefCell<WisphaEntry>>> {
     let thread_pool = Arc::new(Mutex::new(ThreadPool::new(options.threads)?));
-    env::set_var(ROOT_DIR_VAR, file_path.parent().unwrap().to_str().unwrap());
+    let externals = options.externals.clone();
     let intermediate_entry = Arc::new(Mutex::new(WisphaIntermediateEntry::Direct(WisphaDirectEntry::default())));
-    parse_with_env_set(file_path.to_path_buf(), options, Arc::clone(&intermediate_entry), Arc::clone(&thread_pool))?;
+    parse_with_root_dir_set(file_path.to_path_buf(), Arc::new(root_dir.to_path_buf()), options, Arc::clone(&intermediate_entry), Arc::clone(&thread_pool))?;
     let locked_entry = intermediate_entry.lock().unwrap();
     let mut cache = HashMap::new();
+    let mut id_index = HashMap::new();
+    let mut name_index: HashMap<String, Vec<Rc<RefCell<WisphaEntry>>>> = HashMap::new();
+    let mut duplicate_id = None;
     let mut callback = |entry: Rc<RefCell<WisphaEntry>>| {
-        cache.insert((*entry).borrow().properties.absolute_path.clone(), Rc::clone(&entry));
+        if (*entry).borrow().properties.has_own_path() {
+            cache.insert((*entry).borrow().properties.absolute_path.clone(), Rc::clone(&entry));
+        }
+        name_index.entry((*entry).borrow().properties.name.clone()).or_insert_with(Vec::new).push(Rc::clone(&entry));
+        if let Some(id) = (*entry).borrow().properties.id.clone() {
+            if let Some(existing) = id_index.insert(id.clone(), Rc::clone(&entry)) {
+                duplicate_id.get_or_insert((id, (*existing).borrow().properties.file_path.clone(), (*entry).borrow().properties.file_path.clone()));
+            }
+        }
     };
     if let Some(common) = locked_entry.to_common(&mut callback) {
-        resolve_dependencies(Rc::clone(&common), &cache)?;
+        if let Some((id, first_file, second_file)) = duplicate_id {
+            return Err(ParserError::DuplicateId(id, first_file, second_file));
+        }
+        resolve_dependencies(Rc::clone(&common), &cache, &id_index, &name_index, &externals, root_dir)?;
+        build_dependents(Rc::clone(&common));
         Ok(common)
     } else {
         Err(ParserError::Unexpected)
     }
 }
 
-fn parse_with_env_set(file_path: PathBuf,
-                      options: ParserOptions,
-                      this_entry: Arc<Mutex<WisphaIntermediateEntry>>,
-                      thread_pool: Arc<Mutex<ThreadPool>>) -> Result<()> {
+fn parse_with_root_dir_set(file_path: PathBuf,
+                           root_dir: Arc<PathBuf>,
+                           options: ParserOptions,
+                           this_entry: Arc<Mutex<WisphaIntermediateEntry>>,
+                           thread_pool: Arc<Mutex<ThreadPool>>) -> Result<()> {
     let (tx_global, rx_global) = mpsc::channel();
-    parse_with_env_set_sub_routine(file_path, options, Sender::clone(&tx_global), this_entry, thread_pool)?;
+    parse_with_root_dir_set_sub_routine(file_path, root_dir, options, Sender::clone(&tx_global), this_entry, thread_pool)?;
     tx_global.send(Ok(())).or(Err(ParserError::Unexpected))?;
     drop(tx_global);
     let mut counter = 0;
@@ -65,16 +92,17 @@ fn parse_with_env_set(file_path: PathBuf,
     Ok(())
 }
 
-fn parse_with_env_set_sub_routine(file_path: PathBuf,
-                                  options: ParserOptions,
-                                  tx_global: Sender<Result<()>>,
-                                  this_entry: Arc<Mutex<WisphaIntermediateEntry>>,
-                                  thread_pool: Arc<Mutex<ThreadPool>>) -> Result<()> {
+fn parse_with_root_dir_set_sub_routine(file_path: PathBuf,
+                                       root_dir: Arc<PathBuf>,
+                                       options: ParserOptions,
+                                       tx_global: Sender<Result<()>>,
+                                       this_entry: Arc<Mutex<WisphaIntermediateEntry>>,
+                                       thread_pool: Arc<Mutex<ThreadPool>>) -> Result<()> {
     let content = fs::read_to_string(&file_path)
         .or(Err(ParserError::FileCannotRead(file_path.clone())))?;
     let tokens = tokenize(content, &file_path);
-    let root = build_wispha_entry_with_relative_path(tokens, 1, options.clone())?;
-    resolve(root, options.clone(), Sender::clone(&tx_global), this_entry, Arc::clone(&thread_pool))?;
+    let root = build_wispha_entry_with_relative_path(tokens, 1, &root_dir, options.clone())?;
+    resolve(root, Arc::clone(&root_dir), options.clone(), Sender::clone(&tx_global), this_entry, Arc::clone(&thread_pool))?;
     Ok(())
 }
 
@@ -110,9 +138,9 @@ fn parse_line(line_content: String, line_number: usize, file_path: &Path) -> Wis
     wispha_token
 }
 
-fn build_wispha_entry_with_relative_path(tokens: Vec<Rc<WisphaToken>>, depth: usize, options: ParserOptions) -> Result<Arc<Mutex<WisphaIntermediateEntry>>> {
+fn build_wispha_entry_with_relative_path(tokens: Vec<Rc<WisphaToken>>, depth: usize, root_dir: &Path, options: ParserOptions) -> Result<Arc<Mutex<WisphaIntermediateEntry>>> {
     let properties = build_wispha_properties(tokens, depth)?;
-    build_wispha_entry_with_relative_path_from_properties(properties, options)
+    build_wispha_entry_with_relative_path_from_properties(properties, root_dir, options)
 }
 
 fn build_wispha_properties(tokens: Vec<Rc<WisphaToken>>, depth: usize) -> Result<Vec<WisphaRawProperty>> {
@@ -146,7 +174,7 @@ fn build_wispha_properties(tokens: Vec<Rc<WisphaToken>>, depth: usize) -> Result
     Ok(properties)
 }
 
-fn build_wispha_entry_with_relative_path_from_properties(properties: Vec<WisphaRawProperty>, options: ParserOptions) -> Result<Arc<Mutex<WisphaIntermediateEntry>>> {
+fn build_wispha_entry_with_relative_path_from_properties(properties: Vec<WisphaRawProperty>, root_dir: &Path, options: ParserOptions) -> Result<Arc<Mutex<WisphaIntermediateEntry>>> {
     let mut file_path_property = None;
     for property in &properties {
         if property.header.raw_token().content == ENTRY_FILE_PATH_HEADER.to_string() {
@@ -154,9 +182,9 @@ fn build_wispha_entry_with_relative_path_from_properties(properties: Vec<WisphaR
         }
     }
     if let Some(file_path_property) = file_path_property {
-        return build_wispha_link_entry(file_path_property);
+        return build_wispha_link_entry(file_path_property, root_dir);
     } else {
-        return build_wispha_direct_entry(properties, options);
+        return build_wispha_direct_entry(properties, root_dir, options);
     }
 }
 
@@ -199,15 +227,29 @@ fn get_multiline_content_tokens_from_body(body: Vec<Rc<WisphaToken>>) -> Result<
         }
         token_index += 1;
     }
+    // blank lines separating this property from the next one are not content
+    while content_tokens.last().map(|token| token.raw_token().content.trim().is_empty()).unwrap_or(false) {
+        content_tokens.pop();
+    }
     Ok(content_tokens)
 }
 
-fn build_wispha_link_entry(file_path_property: WisphaRawProperty) -> Result<Arc<Mutex<WisphaIntermediateEntry>>> {
+fn get_line_range_of_property(property: &WisphaRawProperty) -> (usize, usize) {
+    let header_line = property.header.raw_token().line_number;
+    let last_line = property.body.iter()
+        .filter(|token| !token.raw_token().content.trim().is_empty())
+        .map(|token| token.raw_token().line_number)
+        .max()
+        .unwrap_or(header_line);
+    (header_line, last_line)
+}
+
+fn build_wispha_link_entry(file_path_property: WisphaRawProperty, root_dir: &Path) -> Result<Arc<Mutex<WisphaIntermediateEntry>>> {
     if let Some(content_token) = get_content_token_from_body(file_path_property.body)? {
         let raw = content_token.raw_token().content.clone();
         let current_dir = content_token.raw_token().file_path.clone().parent().unwrap().to_path_buf();
         Ok(Arc::new(Mutex::new(WisphaIntermediateEntry::Link(WisphaLinkEntry {
-            entry_file_path: actual_path(&raw, &current_dir)?
+            entry_file_path: actual_path(&raw, &current_dir, root_dir)?
         }))))
     } else {
         let token: &WisphaToken = file_path_property.header.borrow();
@@ -215,17 +257,20 @@ fn build_wispha_link_entry(file_path_property: WisphaRawProperty) -> Result<Arc<
     }
 }
 
-fn build_wispha_direct_entry(properties: Vec<WisphaRawProperty>, options: ParserOptions) -> Result<Arc<Mutex<WisphaIntermediateEntry>>> {
+fn build_wispha_direct_entry(properties: Vec<WisphaRawProperty>, root_dir: &Path, options: ParserOptions) -> Result<Arc<Mutex<WisphaIntermediateEntry>>> {
     let mut direct_entry = WisphaDirectEntry::default();
     for property in properties {
         direct_entry.properties.file_path = property.header.raw_token().file_path.clone();
         let header_str = property.header.raw_token().content.as_str();
+        if header_str != SUB_ENTRIES_HEADER {
+            direct_entry.properties.property_lines.insert(header_str.to_string(), get_line_range_of_property(&property));
+        }
         match header_str {
             ABSOLUTE_PATH_HEADER => {
                 if let Some(content_token) = get_content_token_from_body(property.body)? {
                     let raw = content_token.raw_token().content.trim().to_string();
                     let current_dir = content_token.raw_token().file_path.clone().parent().unwrap().to_path_buf();
-                    direct_entry.properties.absolute_path = actual_path(&raw, &current_dir)?
+                    direct_entry.properties.absolute_path = actual_path(&raw, &current_dir, root_dir)?
                 } else {
                     let token: &WisphaToken = property.header.borrow();
                     return Err(ParserError::EmptyBody(token.clone()));
@@ -242,7 +287,7 @@ fn build_wispha_direct_entry(properties: Vec<WisphaRawProperty>, options: Parser
             ENTRY_TYPE_HEADER => {
                 if let Some(content_token) = get_content_token_from_body(property.body)? {
                     let token: &WisphaToken = content_token.borrow();
-                    direct_entry.properties.entry_type = WisphaEntryType::from(content_token.raw_token().content.trim().to_string())
+                    direct_entry.properties.entry_type = WisphaEntryType::from_with_custom_types(content_token.raw_token().content.trim().to_string(), &options.custom_entry_types)
                         .ok_or(ParserError::UnrecognizedEntryFileType(token.clone()))?;
                 } else {
                     let token: &WisphaToken = property.header.borrow();
@@ -261,8 +306,50 @@ fn build_wispha_direct_entry(properties: Vec<WisphaRawProperty>, options: Parser
                 }
                 direct_entry.properties.description = Some(content);
             }
+            FINGERPRINT_HEADER => {
+                if let Some(content_token) = get_content_token_from_body(property.body)? {
+                    direct_entry.properties.fingerprint = Some(content_token.raw_token().content.trim().to_string());
+                } else {
+                    let token: &WisphaToken = property.header.borrow();
+                    return Err(ParserError::EmptyBody(token.clone()));
+                }
+            }
+            ID_HEADER => {
+                if let Some(content_token) = get_content_token_from_body(property.body)? {
+                    direct_entry.properties.id = Some(content_token.raw_token().content.trim().to_string());
+                } else {
+                    let token: &WisphaToken = property.header.borrow();
+                    return Err(ParserError::EmptyBody(token.clone()));
+                }
+            }
+            KIND_HEADER => {
+                if let Some(content_token) = get_content_token_from_body(property.body)? {
+                    direct_entry.properties.kind = Some(content_token.raw_token().content.trim().to_string());
+                } else {
+                    let token: &WisphaToken = property.header.borrow();
+                    return Err(ParserError::EmptyBody(token.clone()));
+                }
+            }
+            LINE_RANGE_HEADER => {
+                if let Some(content_token) = get_content_token_from_body(property.body)? {
+                    let token: &WisphaToken = content_token.borrow();
+                    direct_entry.properties.line_range = Some(parse_line_range(&content_token.raw_token().content)
+                        .ok_or(ParserError::InvalidLineRange(token.clone()))?);
+                } else {
+                    let token: &WisphaToken = property.header.borrow();
+                    return Err(ParserError::EmptyBody(token.clone()));
+                }
+            }
+            SYMBOL_HEADER => {
+                if let Some(content_token) = get_content_token_from_body(property.body)? {
+                    direct_entry.properties.symbol = Some(content_token.raw_token().content.trim().to_string());
+                } else {
+                    let token: &WisphaToken = property.header.borrow();
+                    return Err(ParserError::EmptyBody(token.clone()));
+                }
+            }
             SUB_ENTRIES_HEADER => {
-                let sub_entry = build_wispha_entry_with_relative_path(property.body, property.header.depth().unwrap() + 1, options.clone())?;
+                let sub_entry = build_wispha_entry_with_relative_path(property.body, property.header.depth().unwrap() + 1, root_dir, options.clone())?;
                 let mut locked_sub_entries = direct_entry.sub_entries.lock().unwrap();
                 locked_sub_entries.push(Arc::clone(&sub_entry));
                 drop(locked_sub_entries);
@@ -271,7 +358,19 @@ fn build_wispha_direct_entry(properties: Vec<WisphaRawProperty>, options: Parser
                 if let Some(content_token) = get_content_token_from_body(property.body)? {
                     let raw = content_token.raw_token().content.trim().to_string();
                     let current_dir = content_token.raw_token().file_path.clone().parent().unwrap().to_path_buf();
-                    direct_entry.dependency_path_bufs.lock().unwrap().push(actual_path(&raw, &current_dir)?);
+                    let reference = actual_reference(&raw, &current_dir, &options.externals, root_dir)?;
+                    direct_entry.properties.dependency_lines.entry(reference.clone()).or_insert(property.header.raw_token().line_number);
+                    direct_entry.dependency_path_bufs.lock().unwrap().push(reference);
+                } else {
+                    let token: &WisphaToken = property.header.borrow();
+                    return Err(ParserError::EmptyBody(token.clone()));
+                }
+            }
+            MEMBER_HEADER => {
+                if let Some(content_token) = get_content_token_from_body(property.body)? {
+                    let raw = content_token.raw_token().content.trim().to_string();
+                    let current_dir = content_token.raw_token().file_path.clone().parent().unwrap().to_path_buf();
+                    direct_entry.properties.member_path_bufs.push(actual_reference(&raw, &current_dir, &options.externals, root_dir)?);
                 } else {
                     let token: &WisphaToken = property.header.borrow();
                     return Err(ParserError::EmptyBody(token.clone()));
@@ -279,9 +378,26 @@ fn build_wispha_direct_entry(properties: Vec<WisphaRawProperty>, options: Parser
             }
             _ => {
                 let properties = &options.properties;
+                if !properties.iter().any(|config_property| config_property.name.as_str() == header_str) {
+                    if options.keep_undeclared_properties {
+                        let mut content = String::new();
+                        let content_tokens = get_multiline_content_tokens_from_body(property.body)?;
+                        for token in &content_tokens {
+                            content.push_str(&token.raw_token().content);
+                            content.push_str("\n");
+                        }
+                        if content_tokens.len() > 0 {
+                            content.pop();
+                        }
+                        direct_entry.properties.customized.insert(header_str.to_string(), content);
+                    }
+                    continue;
+                }
                 for config_property in properties {
                     if config_property.name.as_str() == header_str {
-                        let body = if config_property.allow_multi_line == Some(true) {
+                        let schema = options.schemas.get(header_str);
+                        let is_list = schema.map(|schema| schema.is_list()).unwrap_or(false);
+                        let body = if config_property.allow_multi_line == Some(true) || is_list {
                             let mut content = String::new();
                             let content_tokens = get_multiline_content_tokens_from_body(property.body)?;
                             for token in &content_tokens {
@@ -300,6 +416,24 @@ fn build_wispha_direct_entry(properties: Vec<WisphaRawProperty>, options: Parser
                                 return Err(ParserError::EmptyBody(header.clone()));
                             }
                         };
+                        if let (Some(schema), true) = (schema, options.check_values) {
+                            if let Err(reason) = schema.validate(&body) {
+                                let header: &WisphaToken = property.header.borrow();
+                                return Err(ParserError::InvalidPropertyValue(header.clone(), config_property.name.clone(), reason));
+                            }
+                        }
+                        // repeated headers of a list property add items to it. Other properties take the last value,
+                        // and the repetition is reported by `lint`
+                        let body = match direct_entry.properties.customized.get(header_str) {
+                            Some(existing) if is_list => [existing.clone(), body].join(LINE_SEPARATOR),
+                            Some(_) => {
+                                if !direct_entry.properties.repeated_properties.contains(&config_property.name) {
+                                    direct_entry.properties.repeated_properties.push(config_property.name.clone());
+                                }
+                                body
+                            },
+                            None => body,
+                        };
                         direct_entry.properties.customized.insert(config_property.name.clone(), body);
                         break;
                     }
@@ -313,6 +447,7 @@ fn build_wispha_direct_entry(properties: Vec<WisphaRawProperty>, options: Parser
 
 // resolve `entry`, and transfer all its field to `this_entry`. `entry` may be link or direct, `this_entry` is direct.
 fn resolve(entry: Arc<Mutex<WisphaIntermediateEntry>>,
+           root_dir: Arc<PathBuf>,
            options: ParserOptions,
            tx_global: Sender<Result<()>>,
            this_entry: Arc<Mutex<WisphaIntermediateEntry>>,
@@ -330,7 +465,7 @@ fn resolve(entry: Arc<Mutex<WisphaIntermediateEntry>>,
             let mut locked_sub_entries = direct_entry.sub_entries.lock().unwrap();
             for sub_entry in &mut *locked_sub_entries {
                 let this_sub_entry = Arc::new(Mutex::new(WisphaIntermediateEntry::Direct(WisphaDirectEntry::default())));
-                resolve(Arc::clone(sub_entry), options.clone(), Sender::clone(&tx_global), Arc::clone(&this_sub_entry), Arc::clone(&thread_pool))?;
+                resolve(Arc::clone(sub_entry), Arc::clone(&root_dir), options.clone(), Sender::clone(&tx_global), Arc::clone(&this_sub_entry), Arc::clone(&thread_pool))?;
                 let mut locked_this_sub_entry = this_sub_entry.lock().unwrap();
                 locked_this_sub_entry.get_direct_entry_mut().unwrap().sup_entry = Mutex::new(Arc::downgrade(&this_entry));
                 drop(locked_this_sub_entry);
@@ -348,11 +483,12 @@ fn resolve(entry: Arc<Mutex<WisphaIntermediateEntry>>,
             let file_path = link_entry.entry_file_path.clone();
             drop(locked_entry);
             let cloned_tx = Sender::clone(&tx_global);
+            let cloned_root_dir = Arc::clone(&root_dir);
             let cloned_options = options.clone();
             let cloned_thread_pool = Arc::clone(&thread_pool);
             thread_pool.lock().unwrap().execute(move || {
                 let tx_global = cloned_tx;
-                let result = parse_with_env_set_sub_routine(file_path, cloned_options, Sender::clone(&tx_global), this_entry, cloned_thread_pool);
+                let result = parse_with_root_dir_set_sub_routine(file_path, cloned_root_dir, cloned_options, Sender::clone(&tx_global), this_entry, cloned_thread_pool);
                 tx_global.send(result).unwrap();
             });
         }
@@ -361,21 +497,120 @@ fn resolve(entry: Arc<Mutex<WisphaIntermediateEntry>>,
     Ok(())
 }
 
-fn resolve_dependencies(common: Rc<RefCell<WisphaEntry>>, cache: &HashMap<PathBuf, Rc<RefCell<WisphaEntry>>>) -> Result<()> {
-    let mut dependencies = vec![];
+// names and globs are expanded to all entries they match other than the entry itself, and those matching nothing
+// are left to lint. References to other projects are kept to be resolved when followed
+fn resolve_dependencies(common: Rc<RefCell<WisphaEntry>>,
+                        cache: &HashMap<PathBuf, Rc<RefCell<WisphaEntry>>>,
+                        id_index: &HashMap<String, Rc<RefCell<WisphaEntry>>>,
+                        name_index: &HashMap<String, Vec<Rc<RefCell<WisphaEntry>>>>,
+                        externals: &HashMap<String, String>,
+                        root_dir: &Path) -> Result<()> {
+    let mut dependencies: Vec<Rc<RefCell<WisphaEntry>>> = vec![];
+    let mut dependency_references = vec![];
+    let mut unmatched_dependencies = vec![];
+    let mut external_dependencies = vec![];
     for dependency_path_buf in &*(*common).borrow().dependency_path_bufs.borrow() {
-        if let Some(dependency) = cache.get(dependency_path_buf) {
-            dependencies.push(Rc::downgrade(dependency));
+        if external_of_reference(dependency_path_buf, externals).is_some() {
+            external_dependencies.push(dependency_path_buf.clone());
+        } else if is_pattern_reference(dependency_path_buf) {
+            let matched: Vec<Rc<RefCell<WisphaEntry>>> = resolve_pattern_reference(dependency_path_buf, cache, name_index, root_dir).into_iter()
+                .filter(|dependency| !Rc::ptr_eq(dependency, &common))
+                .collect();
+            if matched.is_empty() {
+                unmatched_dependencies.push(dependency_path_buf.clone());
+            }
+            for dependency in matched {
+                if !dependencies.iter().any(|existing| Rc::ptr_eq(existing, &dependency)) {
+                    dependencies.push(dependency);
+                    dependency_references.push(dependency_path_buf.clone());
+                }
+            }
+        } else if let Some(dependency) = resolve_reference(dependency_path_buf, cache, id_index) {
+            dependencies.push(Rc::clone(dependency));
+            dependency_references.push(dependency_path_buf.clone());
+        } else {
+            // left to lint, which reports it at its line
+            unmatched_dependencies.push(dependency_path_buf.clone());
+        }
+    }
+    *(*common).borrow().dependencies.borrow_mut() = dependencies.iter().map(Rc::downgrade).collect();
+    *(*common).borrow().dependency_references.borrow_mut() = dependency_references;
+    *(*common).borrow().unmatched_dependencies.borrow_mut() = unmatched_dependencies;
+    *(*common).borrow().external_dependencies.borrow_mut() = external_dependencies;
+    let mut members = vec![];
+    for member_path_buf in &(*common).borrow().properties.member_path_bufs {
+        if let Some(member) = resolve_reference(member_path_buf, cache, id_index) {
+            members.push(Rc::downgrade(member));
         } else {
-            return Err(ParserError::DependencyNotFound(dependency_path_buf.clone()));
+            return Err(ParserError::MemberNotFound(member_path_buf.clone()));
         }
     }
+    *(*common).borrow().members.borrow_mut() = members;
+    // broken links are left to lint
+    let mut links = HashMap::new();
+    if let Some(description) = &(*common).borrow().properties.description {
+        let current_dir = (*common).borrow().properties.file_path.parent().unwrap().to_path_buf();
+        for target in description_links(description) {
+            let linked = actual_reference(&target, &current_dir, externals, root_dir).ok()
+                .and_then(|reference| resolve_reference(&reference, cache, id_index).map(Rc::downgrade));
+            if let Some(linked) = linked {
+                links.insert(target, linked);
+            }
+        }
+    }
+    *(*common).borrow().links.borrow_mut() = links;
     for sub_entry in &*(*common).borrow().sub_entries.borrow() {
-        resolve_dependencies(Rc::clone(sub_entry), cache)?;
+        resolve_dependencies(Rc::clone(sub_entry), cache, id_index, name_index, externals, root_dir)?;
     }
     Ok(())
 }
 
+// the reverse index of dependencies
+fn build_dependents(common: Rc<RefCell<WisphaEntry>>) {
+    for dependency in &*(*common).borrow().dependencies.borrow() {
+        if let Some(dependency) = dependency.upgrade() {
+            (*dependency).borrow().dependents.borrow_mut().push(Rc::downgrade(&common));
+        }
+    }
+    for sub_entry in &*(*common).borrow().sub_entries.borrow() {
+        build_dependents(Rc::clone(sub_entry));
+    }
+}
+
+fn resolve_reference<'a>(reference: &PathBuf,
+                         cache: &'a HashMap<PathBuf, Rc<RefCell<WisphaEntry>>>,
+                         id_index: &'a HashMap<String, Rc<RefCell<WisphaEntry>>>) -> Option<&'a Rc<RefCell<WisphaEntry>>> {
+    match id_of_reference(reference) {
+        Some(id) => id_index.get(id),
+        None => cache.get(reference),
+    }
+}
+
+// entries with the name, or entries whose paths match the glob, ordered by path
+fn resolve_pattern_reference(reference: &PathBuf,
+                             cache: &HashMap<PathBuf, Rc<RefCell<WisphaEntry>>>,
+                             name_index: &HashMap<String, Vec<Rc<RefCell<WisphaEntry>>>>,
+                             root_dir: &Path) -> Vec<Rc<RefCell<WisphaEntry>>> {
+    if let Some(name) = name_of_reference(reference) {
+        return name_index.get(name).cloned().unwrap_or(vec![]);
+    }
+    // globs are gitignore-style patterns anchored at the root directory, and match nothing outside it
+    let pattern = match reference.strip_prefix(root_dir).ok().and_then(|relative_path| relative_path.to_str()) {
+        Some(relative_path) => format!("/{}", relative_path),
+        None => return vec![],
+    };
+    let mut builder = GitignoreBuilder::new(root_dir);
+    let matcher = match builder.add_line(None, &pattern).ok().and_then(|builder| builder.build().ok()) {
+        Some(matcher) => matcher,
+        None => return vec![],
+    };
+    let mut matched: Vec<(&PathBuf, &Rc<RefCell<WisphaEntry>>)> = cache.iter()
+        .filter(|(path, _)| path.starts_with(root_dir) && **path != root_dir && matcher.matched(path, path.is_dir()).is_ignore())
+        .collect();
+    matched.sort_by(|(path, _), (other_path, _)| path.cmp(other_path));
+    matched.into_iter().map(|(_, entry)| Rc::clone(entry)).collect()
+}
+
 fn is_token_expected(token: &WisphaToken, expected_tokens: &Option<Vec<(WisphaToken, Vec<WisphaExpectOption>)>>) -> bool {
     if let Some(expected_tokens) = &expected_tokens {
         for (expected_token, options) in expected_tokens {
@@ -389,14 +624,23 @@ fn is_token_expected(token: &WisphaToken, expected_tokens: &Option<Vec<(WisphaTo
     }
 }
 
-fn actual_path(raw: &String, current_dir: &PathBuf) -> Result<PathBuf> {
+// references like `id:xyz`, `name:parser` or `corelib:$ROOT_DIR/src/x.rs` are kept as they are, and resolved later.
+// Only projects declared in `externals` are referred to by their aliases
+fn actual_reference(raw: &String, current_dir: &PathBuf, externals: &HashMap<String, String>, root_dir: &Path) -> Result<PathBuf> {
+    if raw.starts_with(ID_REFERENCE_PREFIX) || raw.starts_with(NAME_REFERENCE_PREFIX) || external_of_reference(&PathBuf::from(raw), externals).is_some() {
+        Ok(PathBuf::from(raw))
+    } else {
+        actual_path(raw, current_dir, root_dir)
+    }
+}
+
+fn actual_path(raw: &String, current_dir: &PathBuf, root_dir: &Path) -> Result<PathBuf> {
     let raw = PathBuf::from(raw);
     if raw.is_absolute() {
         return Ok(raw);
     }
 
     if raw.starts_with(ROOT_DIR) {
-        let root_dir = PathBuf::from(env::var(ROOT_DIR_VAR).or(Err(ParserError::EnvNotFound))?);
         let relative_path = raw.strip_prefix(ROOT_DIR).unwrap().to_path_buf();
         return Ok(root_dir.join(relative_path));
     }
@@ -404,3 +648,91 @@ fn actual_path(raw: &String, current_dir: &PathBuf) -> Result<PathBuf> {
     // `raw` is not absolute and not starts with `ROOT_DIR`
     Ok(current_dir.join(&raw))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_reader::{PropertyConfig, property_schemas};
+
+    fn property(name: &str, property_type: &str) -> PropertyConfig {
+        PropertyConfig {
+            name: name.to_string(),
+            default_value: None,
+            allow_multi_line: None,
+            source: None,
+            inherit: None,
+            property_type: Some(property_type.to_string()),
+            values: None,
+            required: None,
+            pattern: None,
+        }
+    }
+
+    fn root_dir_of(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("wispha-parser-{}-{}", name, std::process::id()))
+    }
+
+    // `content` is the root `.wispha` file of a project in a directory of its own
+    fn parse_content(name: &str, content: &str, properties: Vec<PropertyConfig>) -> Result<Rc<RefCell<WisphaEntry>>> {
+        let root_dir = root_dir_of(name);
+        fs::create_dir_all(&root_dir).unwrap();
+        let file_path = root_dir.join(DEFAULT_FILE_NAME_STR);
+        fs::write(&file_path, content).unwrap();
+        let mut options = ParserOptions::default();
+        options.schemas = property_schemas(&properties);
+        options.properties = properties;
+        options.threads = 1;
+        let result = parse_with_root_dir(&file_path, &root_dir, options);
+        fs::remove_dir_all(&root_dir).unwrap();
+        result
+    }
+
+    fn sub_entry_named(entry: &Rc<RefCell<WisphaEntry>>, name: &str) -> Rc<RefCell<WisphaEntry>> {
+        let entry: &RefCell<WisphaEntry> = entry.borrow();
+        let sub_entries = entry.borrow().sub_entries.borrow().clone();
+        sub_entries.into_iter()
+            .find(|sub_entry| (**sub_entry).borrow().properties.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn repeated_headers() {
+        let content = "+ [file path]\n$ROOT_DIR/\n\n+ [name]\nroot\n\n+ [entry type]\ndirectory\n\n\
+                       + [Owner]\ncore\n\n+ [Owner]\nnet\n\n+ [Tags]\napi\n\n+ [Tags]\nnet\n";
+        let root = parse_content("repeated", content, vec![property("Owner", "string"), property("Tags", "list")]).ok().unwrap();
+        let properties = &(*root).borrow().properties;
+        assert_eq!(properties.customized["Owner"], "net");
+        assert_eq!(properties.customized["Tags"], "api\nnet");
+        assert_eq!(properties.repeated_properties, vec!["Owner".to_string()]);
+    }
+
+    #[test]
+    fn duplicate_ids() {
+        let content = "+ [file path]\n$ROOT_DIR/\n\n+ [name]\nroot\n\n+ [entry type]\ndirectory\n\n+ [id]\nsame\n\n\
+                       + [subentry]\n++ [file path]\n$ROOT_DIR/a\n\n++ [name]\na\n\n++ [entry type]\nfile\n\n++ [id]\nsame\n";
+        match parse_content("duplicate", content, vec![]) {
+            Err(ParserError::DuplicateId(id, _, _)) => assert_eq!(id, "same"),
+            _ => panic!("duplicate ids are not reported"),
+        }
+    }
+
+    #[test]
+    fn glob_and_name_dependencies() {
+        let content = "+ [file path]\n$ROOT_DIR/\n\n+ [name]\nroot\n\n+ [entry type]\ndirectory\n\n\
+                       + [subentry]\n++ [file path]\n$ROOT_DIR/a.rs\n\n++ [name]\na.rs\n\n++ [entry type]\nfile\n\n\
+                       ++ [dependency]\n$ROOT_DIR/*.rs\n\n++ [dependency]\nname:c.rs\n\n++ [dependency]\nname:nothing\n\n\
+                       ++ [dependency]\n$ROOT_DIR/missing\n\n++ [dependency]\nid:missing\n\n\
+                       + [subentry]\n++ [file path]\n$ROOT_DIR/b.rs\n\n++ [name]\nb.rs\n\n++ [entry type]\nfile\n\n\
+                       + [subentry]\n++ [file path]\n$ROOT_DIR/c.rs\n\n++ [name]\nc.rs\n\n++ [entry type]\nfile\n";
+        let root = parse_content("dependencies", content, vec![]).ok().unwrap();
+        let a = sub_entry_named(&root, "a.rs");
+        let a: &RefCell<WisphaEntry> = a.borrow();
+        // the entry is not its own dependency, and entries matched twice are kept once
+        let names: Vec<String> = a.borrow().dependencies.borrow().iter()
+            .map(|dependency| (*dependency.upgrade().unwrap()).borrow().properties.name.clone())
+            .collect();
+        assert_eq!(names, vec!["b.rs".to_string(), "c.rs".to_string()]);
+        let unmatched = a.borrow().unmatched_dependencies.borrow().clone();
+        assert_eq!(unmatched, vec![PathBuf::from("name:nothing"), root_dir_of("dependencies").join("missing"), PathBuf::from("id:missing")]);
+    }
+}
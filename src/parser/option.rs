@@ -1,8 +1,9 @@
 use std::error::Error;
 use std::fmt;
 use std::fmt::{Display, Formatter, Debug};
+use std::collections::HashMap;
 
-use crate::config_reader::{Config, PropertyConfig};
+use crate::config_reader::{Config, PropertyConfig, property_schemas, custom_entry_type_names, schema::PropertySchema};
 use crate::strings::*;
 use crate::commandline::Look;
 
@@ -11,6 +12,11 @@ type Result<T> = std::result::Result<T, ParserOptionError>;
 #[derive(Clone)]
 pub struct ParserOptions {
     pub properties: Vec<PropertyConfig>,
+    pub schemas: HashMap<String, PropertySchema>,
+    pub custom_entry_types: Vec<String>,
+    pub keep_undeclared_properties: bool, // properties not declared in config are dropped unless this is set
+    pub check_values: bool, // whether values not conforming to their schemas are errors
+    pub externals: HashMap<String, String>, // alias of project and the path of its root `.wispha` file, relative to the root directory
     pub threads: usize,
 }
 
@@ -18,6 +24,11 @@ impl ParserOptions {
     pub fn default() -> ParserOptions {
         ParserOptions {
             properties: vec![],
+            schemas: HashMap::new(),
+            custom_entry_types: vec![],
+            keep_undeclared_properties: false,
+            check_values: true,
+            externals: HashMap::new(),
             threads: DEFAULT_THREADS,
         }
     }
@@ -31,6 +42,13 @@ impl ParserOptions {
     pub fn update_from_config(&mut self, config: &Config) -> Result<()> {
         if let Some(properties) = &config.properties {
             self.properties = properties.clone();
+            self.schemas = property_schemas(properties);
+        }
+        if let Some(entry_types) = &config.entry_types {
+            self.custom_entry_types = custom_entry_type_names(entry_types);
+        }
+        if let Some(externals) = &config.externals {
+            self.externals = externals.clone();
         }
         Ok(())
     }
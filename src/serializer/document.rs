@@ -0,0 +1,193 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use onig::Regex;
+
+use crate::serializer::{ExportedEntries, options::SerializerOptions};
+use crate::wispha::common::*;
+use crate::wispha::core::line_range_string;
+use crate::strings::*;
+
+// a piece of text in a document, which may link to an exported entry by its anchor
+enum Inline {
+    Text(String),
+    Link(String, String),
+}
+
+// properties of an entry, in the order they are shown
+fn rows_of(entry: &Rc<RefCell<WisphaEntry>>, options: &SerializerOptions, exported: &ExportedEntries) -> Vec<(String, Vec<Inline>)> {
+    let borrowed_entry = entry.borrow();
+    let properties = &borrowed_entry.properties;
+    let mut rows = vec![];
+    if properties.has_own_path() {
+        rows.push((ABSOLUTE_PATH_HEADER.to_string(), vec![Inline::Text(path_of_entry(entry).to_str().unwrap().to_string())]));
+    }
+    rows.push((ENTRY_TYPE_HEADER.to_string(), vec![Inline::Text(properties.entry_type.to_str().to_string())]));
+    if let Some(id) = &properties.id {
+        rows.push((ID_HEADER.to_string(), vec![Inline::Text(id.clone())]));
+    }
+    if let Some(kind) = &properties.kind {
+        rows.push((KIND_HEADER.to_string(), vec![Inline::Text(kind.clone())]));
+    }
+    if let Some(line_range) = &properties.line_range {
+        rows.push((LINE_RANGE_HEADER.to_string(), vec![Inline::Text(line_range_string(line_range))]));
+    }
+    if let Some(symbol) = &properties.symbol {
+        rows.push((SYMBOL_HEADER.to_string(), vec![Inline::Text(symbol.clone())]));
+    }
+    let mut names: Vec<&String> = properties.customized.keys().collect();
+    names.sort();
+    for name in names {
+        let value = &properties.customized[name];
+        let is_list = options.schemas.get(name).map(|schema| schema.is_list()).unwrap_or(false);
+        let value = if is_list { list_items(value).join(", ") } else { value.clone() };
+        rows.push((name.clone(), vec![Inline::Text(value)]));
+    }
+    let dependencies: Vec<Rc<RefCell<WisphaEntry>>> = borrowed_entry.dependencies.borrow().iter().filter_map(|dependency| dependency.upgrade()).collect();
+    if !dependencies.is_empty() {
+        rows.push((DEPENDENCY_HEADER.to_string(), inlines_of_entries(&dependencies, exported)));
+    }
+    let members: Vec<Rc<RefCell<WisphaEntry>>> = borrowed_entry.members.borrow().iter().filter_map(|member| member.upgrade()).collect();
+    if !members.is_empty() {
+        rows.push((MEMBER_HEADER.to_string(), inlines_of_entries(&members, exported)));
+    }
+    rows
+}
+
+fn inlines_of_entries(entries: &Vec<Rc<RefCell<WisphaEntry>>>, exported: &ExportedEntries) -> Vec<Inline> {
+    let mut inlines = vec![];
+    for (index, entry) in entries.iter().enumerate() {
+        if index > 0 {
+            inlines.push(Inline::Text(", ".to_string()));
+        }
+        inlines.push(inline_of_entry(entry, entry.borrow().properties.name.clone(), exported));
+    }
+    inlines
+}
+
+// entries pruned by filters cannot be linked to
+fn inline_of_entry(entry: &Rc<RefCell<WisphaEntry>>, text: String, exported: &ExportedEntries) -> Inline {
+    if exported.contains(&Rc::as_ptr(entry)) {
+        Inline::Link(text, anchor_of(entry))
+    } else {
+        Inline::Text(text)
+    }
+}
+
+// links in description become links to their targets, named after the targets. Broken links are kept as they are
+fn description_of(entry: &Rc<RefCell<WisphaEntry>>, exported: &ExportedEntries) -> Option<Vec<Inline>> {
+    let borrowed_entry = entry.borrow();
+    let description = borrowed_entry.properties.description.as_ref()?;
+    let links = borrowed_entry.links.borrow();
+    let regex = Regex::new(LINK_PATTERN).unwrap();
+    let mut inlines = vec![];
+    let mut last_end = 0;
+    for capture in regex.captures_iter(description) {
+        let (start, end) = capture.pos(0).unwrap();
+        inlines.push(Inline::Text(description[last_end..start].to_string()));
+        let target = capture.at(1).unwrap().trim();
+        match links.get(target).and_then(|linked| linked.upgrade()) {
+            Some(linked) => {
+                let name = linked.borrow().properties.name.clone();
+                inlines.push(inline_of_entry(&linked, name, exported));
+            },
+            None => inlines.push(Inline::Text(capture.at(0).unwrap().to_string())),
+        }
+        last_end = end;
+    }
+    inlines.push(Inline::Text(description[last_end..].to_string()));
+    Some(inlines)
+}
+
+// ids are used if present, so that anchors survive moves, otherwise paths relative to the root directory
+fn anchor_of(entry: &Rc<RefCell<WisphaEntry>>) -> String {
+    if let Some(id) = &entry.borrow().properties.id {
+        return format!("id-{}", id);
+    }
+    let path = path_of_entry(entry);
+    let anchor: String = path.to_str().unwrap().chars()
+        .map(|character| if character.is_alphanumeric() { character } else { '-' })
+        .collect();
+    format!("path-{}", anchor)
+}
+
+pub fn convert_to_markdown(entry: &Rc<RefCell<WisphaEntry>>, options: &SerializerOptions, exported: &ExportedEntries) -> String {
+    let mut document = String::new();
+    push_markdown_of_entry(entry, 1, options, exported, &mut document);
+    document
+}
+
+fn push_markdown_of_entry(entry: &Rc<RefCell<WisphaEntry>>, depth: usize, options: &SerializerOptions, exported: &ExportedEntries, document: &mut String) {
+    if !exported.contains(&Rc::as_ptr(entry)) {
+        return;
+    }
+    document.push_str(&format!("<a id=\"{}\"></a>\n\n", anchor_of(entry)));
+    document.push_str(&format!("{} {}\n\n", "#".repeat(depth.min(6)), entry.borrow().properties.name));
+    for (name, value) in rows_of(entry, options, exported) {
+        document.push_str(&format!("- **{}**: {}\n", name, markdown_of_inlines(&value)));
+    }
+    document.push_str("\n");
+    if let Some(description) = description_of(entry, exported) {
+        document.push_str(&markdown_of_inlines(&description));
+        document.push_str("\n\n");
+    }
+    for sub_entry in &*entry.borrow().sub_entries.borrow() {
+        push_markdown_of_entry(sub_entry, depth + 1, options, exported, document);
+    }
+}
+
+fn markdown_of_inlines(inlines: &Vec<Inline>) -> String {
+    inlines.iter()
+        .map(|inline| match inline {
+            Inline::Text(text) => text.clone(),
+            Inline::Link(text, anchor) => format!("[{}](#{})", text, anchor),
+        })
+        .collect()
+}
+
+pub fn convert_to_html(entry: &Rc<RefCell<WisphaEntry>>, options: &SerializerOptions, exported: &ExportedEntries) -> String {
+    let mut document = String::new();
+    document.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    document.push_str(&format!("<title>{}</title>\n", escape_html(&entry.borrow().properties.name)));
+    document.push_str("</head>\n<body>\n");
+    push_html_of_entry(entry, 1, options, exported, &mut document);
+    document.push_str("</body>\n</html>\n");
+    document
+}
+
+fn push_html_of_entry(entry: &Rc<RefCell<WisphaEntry>>, depth: usize, options: &SerializerOptions, exported: &ExportedEntries, document: &mut String) {
+    if !exported.contains(&Rc::as_ptr(entry)) {
+        return;
+    }
+    let heading_level = depth.min(6);
+    document.push_str(&format!("<section id=\"{}\">\n", anchor_of(entry)));
+    document.push_str(&format!("<h{}>{}</h{}>\n", heading_level, escape_html(&entry.borrow().properties.name), heading_level));
+    document.push_str("<dl>\n");
+    for (name, value) in rows_of(entry, options, exported) {
+        document.push_str(&format!("<dt>{}</dt><dd>{}</dd>\n", escape_html(&name), html_of_inlines(&value)));
+    }
+    document.push_str("</dl>\n");
+    if let Some(description) = description_of(entry, exported) {
+        document.push_str(&format!("<p style=\"white-space: pre-wrap\">{}</p>\n", html_of_inlines(&description)));
+    }
+    for sub_entry in &*entry.borrow().sub_entries.borrow() {
+        push_html_of_entry(sub_entry, depth + 1, options, exported, document);
+    }
+    document.push_str("</section>\n");
+}
+
+fn html_of_inlines(inlines: &Vec<Inline>) -> String {
+    inlines.iter()
+        .map(|inline| match inline {
+            Inline::Text(text) => escape_html(text),
+            Inline::Link(text, anchor) => format!("<a href=\"#{}\">{}</a>", anchor, escape_html(text)),
+        })
+        .collect()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
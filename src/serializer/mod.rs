@@ -1,59 +1,184 @@
 pub mod options;
 pub mod error;
 
+mod document;
+
 use options::{SerializerOptions, Language};
 use error::SerializerError;
 
-use crate::wispha::common::WisphaEntry;
+use crate::wispha::common::{WisphaEntry, fill_inherited_properties, path_of_entry};
+use crate::wispha::core::line_range_string;
 use crate::strings::*;
 
 use serde::ser::{Serialize, Serializer, SerializeMap};
 
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{RefCell, Ref};
+use std::collections::HashSet;
 
 type Result<T> = std::result::Result<T, SerializerError>;
 
-impl Serialize for WisphaEntry {
+// entries matching all filters, and their ancestors
+type ExportedEntries = HashSet<*const RefCell<WisphaEntry>>;
+
+// computed once per export, since whether an entry is exported depends on its whole subtree
+fn exported_entries(entry: &Rc<RefCell<WisphaEntry>>, options: &SerializerOptions) -> ExportedEntries {
+    let mut exported = HashSet::new();
+    collect_exported_entries(entry, options, &mut exported);
+    exported
+}
+
+fn collect_exported_entries(entry: &Rc<RefCell<WisphaEntry>>, options: &SerializerOptions, exported: &mut ExportedEntries) -> bool {
+    let borrowed_entry = entry.borrow();
+    let customized = &borrowed_entry.properties.customized;
+    let mut is_exported = options.filters.iter()
+        .all(|filter| filter.matches(|property| customized.get(property).cloned(), &options.list_properties));
+    for sub_entry in &*borrowed_entry.sub_entries.borrow() {
+        if collect_exported_entries(sub_entry, options, exported) {
+            is_exported = true;
+        }
+    }
+    if is_exported {
+        exported.insert(Rc::as_ptr(entry));
+    }
+    is_exported
+}
+
+// values of properties with schemas are serialized in their own types
+struct SerializableEntry<'a> {
+    entry: &'a WisphaEntry,
+    options: &'a SerializerOptions,
+    exported: &'a ExportedEntries,
+}
+
+impl<'a> Serialize for SerializableEntry<'a> {
     fn serialize<S>(&self, serializer: S) -> std::result::Result<<S as Serializer>::Ok, <S as Serializer>::Error>
         where
             S: Serializer
     {
+        let entry = self.entry;
         let mut wispha = serializer.serialize_map(None)?;
-        wispha.serialize_entry(NAME_HEADER, &self.properties.name)?;
-        wispha.serialize_entry(ABSOLUTE_PATH_HEADER, &self.properties.absolute_path.to_str().unwrap())?;
-        wispha.serialize_entry(ENTRY_TYPE_HEADER, &self.properties.entry_type.to_str())?;
-        if let Some(description) = &self.properties.description {
+        wispha.serialize_entry(NAME_HEADER, &entry.properties.name)?;
+        if let Some(id) = &entry.properties.id {
+            wispha.serialize_entry(ID_HEADER, id)?;
+        }
+        if !entry.properties.is_virtual() {
+            wispha.serialize_entry(ABSOLUTE_PATH_HEADER, &entry.properties.absolute_path.to_str().unwrap())?;
+        }
+        wispha.serialize_entry(ENTRY_TYPE_HEADER, &entry.properties.entry_type.to_str())?;
+        if let Some(kind) = &entry.properties.kind {
+            wispha.serialize_entry(KIND_HEADER, kind)?;
+        }
+        if let Some(line_range) = &entry.properties.line_range {
+            wispha.serialize_entry(LINE_RANGE_HEADER, &line_range_string(line_range))?;
+        }
+        if let Some(symbol) = &entry.properties.symbol {
+            wispha.serialize_entry(SYMBOL_HEADER, symbol)?;
+        }
+        if let Some(description) = &entry.properties.description {
             wispha.serialize_entry(DESCRIPTION_HEADER, description)?;
         }
-        for (header, body) in &self.properties.customized {
-            wispha.serialize_entry(header, body)?;
+        if let Some(fingerprint) = &entry.properties.fingerprint {
+            wispha.serialize_entry(FINGERPRINT_HEADER, fingerprint)?;
+        }
+        for (header, body) in &entry.properties.customized {
+            match self.options.schemas.get(header) {
+                Some(schema) => wispha.serialize_entry(header, &schema.typed_value(body))?,
+                None => wispha.serialize_entry(header, body)?,
+            }
+        }
+        let sub_entries = entry.sub_entries.borrow();
+        let sub_entries: Vec<Ref<WisphaEntry>> = sub_entries.iter()
+            .filter(|sub_entry| self.exported.contains(&Rc::as_ptr(sub_entry)))
+            .map(|sub_entry| sub_entry.borrow())
+            .collect();
+        if !sub_entries.is_empty() {
+            let sub_entries: Vec<SerializableEntry> = sub_entries.iter()
+                .map(|sub_entry| SerializableEntry { entry: &*sub_entry, options: self.options, exported: self.exported })
+                .collect();
+            wispha.serialize_entry(SUB_ENTRIES_HEADER, &sub_entries)?;
         }
-        if !self.sub_entries.borrow().is_empty() {
-            wispha.serialize_entry(SUB_ENTRIES_HEADER, &self.sub_entries)?;
+        // dependencies are referred to by their ids or paths, since they may form cycles
+        let dependencies: Vec<String> = entry.dependencies.borrow().iter()
+            .filter_map(|dependency| dependency.upgrade())
+            .map(|dependency| reference_of_entry(&dependency))
+            .collect();
+        if !dependencies.is_empty() {
+            wispha.serialize_entry(DEPENDENCY_HEADER, &dependencies)?;
         }
-        if !self.dependencies.borrow().is_empty() {
-            wispha.serialize_entry(DEPENDENCY_HEADER, &self.dependencies)?;
+        let members: Vec<Option<Rc<RefCell<WisphaEntry>>>> = entry.members.borrow().iter().map(|member| member.upgrade()).collect();
+        if !members.is_empty() {
+            let members: Vec<Option<Ref<WisphaEntry>>> = members.iter()
+                .map(|member| member.as_ref().map(|member| member.borrow()))
+                .collect();
+            let members: Vec<Option<MemberView>> = members.iter()
+                .map(|member| member.as_ref().map(|member| MemberView { entry: &*member }))
+                .collect();
+            wispha.serialize_entry(MEMBER_HEADER, &members)?;
         }
         wispha.end()
     }
 }
 
+// `id:xyz` if the entry has an id, otherwise its path starting with `$ROOT_DIR`
+fn reference_of_entry(entry: &Rc<RefCell<WisphaEntry>>) -> String {
+    match &entry.borrow().properties.id {
+        Some(id) => format!("{}{}", ID_REFERENCE_PREFIX, id),
+        None => path_of_entry(entry).to_str().unwrap().to_string(),
+    }
+}
+
+// a virtual entry shows what its members are, without their subtrees
+struct MemberView<'a> {
+    entry: &'a WisphaEntry,
+}
+
+impl<'a> Serialize for MemberView<'a> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<<S as Serializer>::Ok, <S as Serializer>::Error>
+        where
+            S: Serializer
+    {
+        let properties = &self.entry.properties;
+        let mut member = serializer.serialize_map(None)?;
+        member.serialize_entry(NAME_HEADER, &properties.name)?;
+        if let Some(id) = &properties.id {
+            member.serialize_entry(ID_HEADER, id)?;
+        }
+        member.serialize_entry(ABSOLUTE_PATH_HEADER, &properties.absolute_path.to_str().unwrap())?;
+        member.serialize_entry(ENTRY_TYPE_HEADER, &properties.entry_type.to_str())?;
+        if let Some(description) = &properties.description {
+            member.serialize_entry(DESCRIPTION_HEADER, description)?;
+        }
+        member.end()
+    }
+}
+
+// inherited properties are exported in every entry
 pub fn serialize(entry: Rc<RefCell<WisphaEntry>>, options: SerializerOptions) -> Result<String> {
+    fill_inherited_properties(&entry, &options.inherited_properties);
+    let exported = exported_entries(&entry, &options);
     match &options.language {
         Language::JSON => {
-            convert_to_json(entry)
+            convert_to_json(entry, &options, &exported)
         },
         Language::TOML => {
-            convert_to_toml(entry)
-        }
+            convert_to_toml(entry, &options, &exported)
+        },
+        Language::Markdown => {
+            Ok(document::convert_to_markdown(&entry, &options, &exported))
+        },
+        Language::HTML => {
+            Ok(document::convert_to_html(&entry, &options, &exported))
+        },
     }
 }
 
-fn convert_to_json(entry: Rc<RefCell<WisphaEntry>>) -> Result<String> {
-    serde_json::to_string(&entry).or(Err(SerializerError::SerializeError))
+fn convert_to_json(entry: Rc<RefCell<WisphaEntry>>, options: &SerializerOptions, exported: &ExportedEntries) -> Result<String> {
+    let entry = entry.borrow();
+    serde_json::to_string(&SerializableEntry { entry: &*entry, options, exported }).or(Err(SerializerError::SerializeError))
 }
 
-fn convert_to_toml(entry: Rc<RefCell<WisphaEntry>>) -> Result<String> {
-    toml::to_string(&entry).or(Err(SerializerError::SerializeError))
+fn convert_to_toml(entry: Rc<RefCell<WisphaEntry>>, options: &SerializerOptions, exported: &ExportedEntries) -> Result<String> {
+    let entry = entry.borrow();
+    toml::to_string(&SerializableEntry { entry: &*entry, options, exported }).or(Err(SerializerError::SerializeError))
 }
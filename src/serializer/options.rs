@@ -4,18 +4,38 @@ use std::fmt::{Display, Formatter, Debug};
 
 use crate::strings::*;
 use crate::commandline::Convert;
+use crate::config_reader::{Config, inherited_property_names, list_property_names, property_schemas, schema::PropertySchema};
+use crate::wispha::common::TagFilter;
+
+use std::collections::HashMap;
 
 type Result<T> = std::result::Result<T, SerializerOptionError>;
 
 #[derive(Clone)]
 pub struct SerializerOptions {
     pub language: Language,
+    pub inherited_properties: Vec<String>,
+    pub schemas: HashMap<String, PropertySchema>,
+    pub list_properties: Vec<String>,
+    pub filters: Vec<TagFilter>, // only entries matching all filters, and their ancestors, are exported
 }
 
 impl SerializerOptions {
     pub fn default() -> SerializerOptions {
         SerializerOptions {
             language: DEFAULT_SERIALIZE_LANGUAGE,
+            inherited_properties: vec![],
+            schemas: HashMap::new(),
+            list_properties: vec![],
+            filters: vec![],
+        }
+    }
+
+    pub fn update_from_config(&mut self, config: &Config) {
+        if let Some(properties) = &config.properties {
+            self.inherited_properties = inherited_property_names(properties);
+            self.schemas = property_schemas(properties);
+            self.list_properties = list_property_names(properties);
         }
     }
 
@@ -23,6 +43,7 @@ impl SerializerOptions {
         if let Some(language_str) = &convert.language {
             self.language = Language::from(language_str)?;
         }
+        self.filters = convert.tag.iter().map(|tag| TagFilter::from(tag)).collect();
         Ok(())
     }
 }
@@ -31,6 +52,8 @@ impl SerializerOptions {
 pub enum Language {
     JSON,
     TOML,
+    Markdown, // a document with links between entries
+    HTML,
 }
 
 impl Language {
@@ -39,6 +62,8 @@ impl Language {
         match language_str {
             "JSON" => Ok(JSON),
             "TOML" => Ok(TOML),
+            "Markdown" => Ok(Markdown),
+            "HTML" => Ok(HTML),
             _ => Err(SerializerOptionError::LanguageNotSupport(language_str.to_string()))
         }
     }
@@ -48,6 +73,8 @@ impl Language {
         match &self {
             JSON => String::from("json"),
             TOML => String::from("toml"),
+            Markdown => String::from("md"),
+            HTML => String::from("html"),
         }
     }
 }
@@ -11,6 +11,7 @@ use crate::parser::option::ParserOptions;
 use crate::config_reader;
 use crate::parser;
 use crate::wispha::common::*;
+use crate::annotator;
 
 use std::path::PathBuf;
 use std::rc::Rc;
@@ -19,12 +20,17 @@ use std::fs;
 
 type Result<T> = std::result::Result<T, StatorError>;
 
-pub fn state_from_path(path: &PathBuf, options: StatorOptions) -> Result<Vec<PathBuf>> {
+pub struct StateReport {
+    pub unrecorded_paths: Vec<PathBuf>,
+    pub conflicting_paths: Vec<PathBuf>, // files whose annotations conflict with the `.wispha` file
+}
+
+pub fn state_from_path(path: &PathBuf, options: StatorOptions) -> Result<StateReport> {
     let root_dir = path.parent().unwrap().to_path_buf();
     let ignored = get_ignored_files_from_root(&root_dir, &options.ignored_files)?;
 
     let mut parser_options = ParserOptions::default();
-    let config = config_reader::read_configs_in_dir(&path).or_else(|error| Err(StatorError::ConfigError(error)))?;
+    let config = config_reader::read_configs_in_dir(&root_dir).or_else(|error| Err(StatorError::ConfigError(error)))?;
     if let Some(config) = config {
         parser_options.update_from_config(&config).or_else(|error| Err(StatorError::ParserOptionError(error)))?;
     }
@@ -41,9 +47,30 @@ pub fn state_from_path(path: &PathBuf, options: StatorOptions) -> Result<Vec<Pat
         vec![]
     };
 
+    let mut conflicting_paths = vec![];
+    merge_annotations_into_root(Rc::clone(&root), &mut conflicting_paths, &options);
+
     let mut unrecorded_paths = vec![];
     get_unrecorded_files_from_root(&root.borrow().properties.absolute_path, &mut unrecorded_paths, &recorded_paths, &ignored, &git_files, &options)?;
-    Ok(unrecorded_paths)
+    Ok(StateReport {
+        unrecorded_paths,
+        conflicting_paths,
+    })
+}
+
+fn merge_annotations_into_root(root: Rc<RefCell<WisphaEntry>>, conflicting_paths: &mut Vec<PathBuf>, options: &StatorOptions) {
+    let absolute_path = root.borrow().properties.absolute_path.clone();
+    if absolute_path.is_file() && root.borrow().properties.has_own_path() {
+        if let Some(annotation) = annotator::read_annotation(&absolute_path) {
+            let mut entry = root.borrow_mut();
+            if annotator::merge_annotation(&mut entry.properties, &annotation, &options.properties, options.annotation_preference) {
+                conflicting_paths.push(absolute_path);
+            }
+        }
+    }
+    for sub_entry in &*root.borrow().sub_entries.borrow() {
+        merge_annotations_into_root(Rc::clone(sub_entry), conflicting_paths, options);
+    }
 }
 
 fn get_ignored_files_from_root(root_dir: &PathBuf, ignored_files: &Vec<String>) -> Result<Gitignore> {
@@ -55,8 +82,11 @@ fn get_ignored_files_from_root(root_dir: &PathBuf, ignored_files: &Vec<String>)
     Ok(wispha_ignore)
 }
 
+// virtual entries are not backed by the file system
 fn get_recorded_files_from_root(root: Rc<RefCell<WisphaEntry>>, recorded_paths: &mut Vec<PathBuf>) {
-    recorded_paths.push(root.borrow().properties.absolute_path.clone());
+    if !root.borrow().properties.is_virtual() {
+        recorded_paths.push(root.borrow().properties.absolute_path.clone());
+    }
     for sub_entry in &*root.borrow().sub_entries.borrow() {
         get_recorded_files_from_root(Rc::clone(sub_entry), recorded_paths);
     }
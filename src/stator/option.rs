@@ -1,4 +1,4 @@
-use crate::config_reader::Config;
+use crate::config_reader::{Config, PropertyConfig, AnnotationPreference};
 use crate::commandline::State;
 use crate::strings::*;
 use crate::parser::option::ParserOptions;
@@ -7,6 +7,8 @@ pub struct StatorOptions {
     pub ignored_files: Vec<String>,
     pub allow_hidden_files: bool,
     pub git: bool,
+    pub properties: Vec<PropertyConfig>,
+    pub annotation_preference: AnnotationPreference,
     pub threads: usize,
 }
 
@@ -16,6 +18,8 @@ impl StatorOptions {
             ignored_files: vec![],
             allow_hidden_files: false,
             git: false,
+            properties: vec![],
+            annotation_preference: DEFAULT_ANNOTATION_PREFERENCE,
             threads: DEFAULT_THREADS,
         }
     }
@@ -29,6 +33,14 @@ impl StatorOptions {
                 self.allow_hidden_files = *allow_hidden_files;
             }
         }
+        if let Some(properties) = &config.properties {
+            self.properties = properties.clone();
+        }
+        if let Some(annotation) = &config.annotation {
+            if let Some(prefer) = annotation.prefer {
+                self.annotation_preference = prefer;
+            }
+        }
     }
 
     pub fn update_from_commandline(&mut self, state: &State) {
@@ -1,5 +1,6 @@
 use crate::wispha::core::WisphaEntryType;
 use crate::serializer::options::Language;
+use crate::config_reader::AnnotationPreference;
 
 pub const DEFAULT_ENTRY_TYPE: WisphaEntryType = WisphaEntryType::File;
 pub const DEFAULT_NAME: &str = "default name";
@@ -15,6 +16,7 @@ pub const BEGIN_MARK: &str = "+";
 pub const DIRECTORY_TYPE: &str = "directory";
 pub const FILE_TYPE: &str = "file";
 pub const PROGRAM_ENTRY_TYPE: &str = "program entry";
+pub const VIRTUAL_TYPE: &str = "virtual";
 
 pub const ABSOLUTE_PATH_HEADER: &str = "file path";
 pub const NAME_HEADER: &str = "name";
@@ -23,12 +25,55 @@ pub const DESCRIPTION_HEADER: &str = "description";
 pub const DEPENDENCY_HEADER: &str = "dependency";
 pub const ENTRY_FILE_PATH_HEADER: &str = "entry file path";
 pub const SUB_ENTRIES_HEADER: &str = "subentry";
+pub const FINGERPRINT_HEADER: &str = "fingerprint";
+pub const MEMBER_HEADER: &str = "member";
+pub const ID_HEADER: &str = "id";
+pub const KIND_HEADER: &str = "kind";
+pub const LINE_RANGE_HEADER: &str = "line range";
+pub const SYMBOL_HEADER: &str = "symbol";
+
+pub const ANNOTATION_MARK: &str = "wispha:";
 
 pub const ROOT_DIR: &str = "$ROOT_DIR";
 pub const ROOT_DIR_VAR: &str = "WISPHA_ROOT_DIR";
 
+pub const ID_REFERENCE_PREFIX: &str = "id:";
+pub const NAME_REFERENCE_PREFIX: &str = "name:";
+pub const EXTERNAL_REFERENCE_PATTERN: &str = r"^([A-Za-z_][A-Za-z0-9_\-]*):(.+)$";
+pub const GLOB_CHARS: [char; 3] = ['*', '?', '['];
+pub const ID_LENGTH: usize = 8;
+
+pub const LINK_PATTERN: &str = r"\[\[([^\[\]]+)\]\]";
+
+pub const GIT_LAST_AUTHOR_SOURCE: &str = "git.last_author";
+pub const GIT_LAST_MODIFIED_SOURCE: &str = "git.last_modified";
+pub const GIT_COMMITS_SOURCE: &str = "git.commits";
+pub const METADATA_SIZE_SOURCE: &str = "metadata.size";
+pub const METADATA_LINES_SOURCE: &str = "metadata.lines";
+pub const METADATA_FILES_SOURCE: &str = "metadata.files";
+pub const METADATA_LANGUAGE_SOURCE: &str = "metadata.language";
+pub const METADATA_EXECUTABLE_SOURCE: &str = "metadata.executable";
+
+pub const STRING_PROPERTY_TYPE: &str = "string";
+pub const INTEGER_PROPERTY_TYPE: &str = "integer";
+pub const BOOL_PROPERTY_TYPE: &str = "bool";
+pub const DATE_PROPERTY_TYPE: &str = "date";
+pub const URL_PROPERTY_TYPE: &str = "url";
+pub const ENUM_PROPERTY_TYPE: &str = "enum";
+pub const LIST_PROPERTY_TYPE: &str = "list";
+
 pub const CONFIG_FILE_NAME: &str = ".wispharc";
 
 pub const DEFAULT_THREADS: usize = 4;
 
+pub const FLAT_LAYER: &str = "flat";
+pub const RECURSIVE_LAYER: &str = "recursive";
+pub const HYBRID_LAYER: &str = "hybrid";
+pub const DEFAULT_HYBRID_LEVELS: usize = 1;
+
+pub const DEFAULT_ANNOTATION_PREFERENCE: AnnotationPreference = AnnotationPreference::Source;
+
+pub const DEFAULT_STALE_MAX_AGE: i64 = 30;
+pub const DEFAULT_STALE_MAX_COMMITS: usize = 5;
+
 pub const DEFAULT_SERIALIZE_LANGUAGE: Language = Language::JSON;
\ No newline at end of file
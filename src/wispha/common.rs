@@ -1,7 +1,10 @@
 use std::rc::{Rc, Weak};
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::env;
+
+use onig::Regex;
 
 use crate::wispha::core::*;
 use crate::strings::*;
@@ -12,7 +15,13 @@ pub struct WisphaEntry {
     pub sup_entry: RefCell<Weak<RefCell<WisphaEntry>>>, // for the root node, `*sup_entry` is Weak::new()
     pub sub_entries: RefCell<Vec<Rc<RefCell<WisphaEntry>>>>,
     pub dependencies: RefCell<Vec<Weak<RefCell<WisphaEntry>>>>,
+    pub dependency_references: RefCell<Vec<PathBuf>>, // the reference in `dependency_path_bufs` each of `dependencies` is resolved from
+    pub dependents: RefCell<Vec<Weak<RefCell<WisphaEntry>>>>, // entries whose dependencies contain this one
     pub dependency_path_bufs: RefCell<Vec<PathBuf>>,
+    pub unmatched_dependencies: RefCell<Vec<PathBuf>>, // references in `dependency_path_bufs` matching no entry
+    pub external_dependencies: RefCell<Vec<PathBuf>>, // references to entries in other projects, resolved when followed
+    pub members: RefCell<Vec<Weak<RefCell<WisphaEntry>>>>, // resolved from `properties.member_path_bufs`
+    pub links: RefCell<HashMap<String, Weak<RefCell<WisphaEntry>>>>, // targets of links in description which can be resolved
 }
 
 impl WisphaEntry {
@@ -21,9 +30,18 @@ impl WisphaEntry {
             entry_type: DEFAULT_ENTRY_TYPE,
             name: String::from(DEFAULT_NAME),
             description: None,
+            fingerprint: None,
+            id: None,
+            kind: None,
+            line_range: None,
+            symbol: None,
             absolute_path: PathBuf::from(DEFAULT_PATH),
             file_path: PathBuf::from(DEFAULT_FILE_PATH),
             customized: HashMap::new(),
+            member_path_bufs: vec![],
+            property_lines: HashMap::new(),
+            repeated_properties: vec![],
+            dependency_lines: HashMap::new(),
         };
 
         let sup_entry = RefCell::new(Weak::new());
@@ -32,14 +50,247 @@ impl WisphaEntry {
 
         let dependencies = RefCell::new(Vec::new());
 
+        let dependency_references = RefCell::new(Vec::new());
+
+        let dependents = RefCell::new(Vec::new());
+
         let dependency_path_bufs = RefCell::new(Vec::new());
 
+        let unmatched_dependencies = RefCell::new(Vec::new());
+
+        let external_dependencies = RefCell::new(Vec::new());
+
+        let members = RefCell::new(Vec::new());
+
+        let links = RefCell::new(HashMap::new());
+
         WisphaEntry {
             properties,
             sup_entry,
             sub_entries,
             dependencies,
+            dependency_references,
+            dependents,
             dependency_path_bufs,
+            unmatched_dependencies,
+            external_dependencies,
+            members,
+            links,
+        }
+    }
+}
+
+// the path of `entry` starting with `$ROOT_DIR`, which is the root directory of the project parsed last
+pub fn path_of_entry(entry: &Rc<RefCell<WisphaEntry>>) -> PathBuf {
+    let root_dir = PathBuf::from(env::var(ROOT_DIR_VAR).unwrap());
+    path_of_entry_in(entry, &root_dir)
+}
+
+// the path of `entry` starting with `$ROOT_DIR`, which is `root_dir`. Entries without their own paths are named after their parent entries
+pub fn path_of_entry_in(entry: &Rc<RefCell<WisphaEntry>>, root_dir: &Path) -> PathBuf {
+    if !(*entry).borrow().properties.has_own_path() {
+        let name = (*entry).borrow().properties.name.clone();
+        return match (*entry).borrow().sup_entry.borrow().upgrade() {
+            Some(sup_entry) => path_of_entry_in(&sup_entry, root_dir).join(name),
+            None => PathBuf::from(ROOT_DIR).join(name),
+        };
+    }
+    let raw = (*entry)
+        .borrow()
+        .properties
+        .absolute_path.clone();
+    if raw.starts_with(root_dir) {
+        PathBuf::from(ROOT_DIR).join(raw.strip_prefix(root_dir).unwrap().to_path_buf())
+    } else {
+        PathBuf::from(ROOT_DIR).join(raw)
+    }
+}
+
+// `target` is canonicalized
+pub fn find_entry_by_path(root: Rc<RefCell<WisphaEntry>>, target: &PathBuf) -> Option<Rc<RefCell<WisphaEntry>>> {
+    if root.borrow().properties.absolute_path.canonicalize().ok().as_ref() == Some(target) {
+        return Some(root);
+    }
+    for sub_entry in &*root.borrow().sub_entries.borrow() {
+        if let Some(entry) = find_entry_by_path(Rc::clone(sub_entry), target) {
+            return Some(entry);
         }
     }
-}
\ No newline at end of file
+    None
+}
+
+// the value of customized property `name` of `entry`, or of its nearest ancestor having it,
+// with the entry where the value is recorded
+pub fn find_inherited_property(entry: &Rc<RefCell<WisphaEntry>>, name: &str) -> Option<(String, Rc<RefCell<WisphaEntry>>)> {
+    let mut current = Rc::clone(entry);
+    loop {
+        if let Some(value) = current.borrow().properties.customized.get(name) {
+            return Some((value.clone(), Rc::clone(&current)));
+        }
+        let sup_entry = current.borrow().sup_entry.borrow().upgrade()?;
+        current = sup_entry;
+    }
+}
+
+// record values of inherited properties in every entry lacking them
+pub fn fill_inherited_properties(root: &Rc<RefCell<WisphaEntry>>, names: &Vec<String>) {
+    for sub_entry in &*root.borrow().sub_entries.borrow() {
+        for name in names {
+            if sub_entry.borrow().properties.customized.contains_key(name) {
+                continue;
+            }
+            if let Some(value) = root.borrow().properties.customized.get(name) {
+                sub_entry.borrow_mut().properties.customized.insert(name.clone(), value.clone());
+            }
+        }
+        fill_inherited_properties(sub_entry, names);
+    }
+}
+
+// the id referred to by a reference like `id:xyz` in place of a path
+pub fn id_of_reference(reference: &PathBuf) -> Option<&str> {
+    reference.to_str()
+        .filter(|reference| reference.starts_with(ID_REFERENCE_PREFIX))
+        .map(|reference| &reference[ID_REFERENCE_PREFIX.len()..])
+}
+
+// the name referred to by a reference like `name:parser` in place of a path
+pub fn name_of_reference(reference: &PathBuf) -> Option<&str> {
+    reference.to_str()
+        .filter(|reference| reference.starts_with(NAME_REFERENCE_PREFIX))
+        .map(|reference| &reference[NAME_REFERENCE_PREFIX.len()..])
+}
+
+// the alias of the project and the reference in it of a reference like `corelib:$ROOT_DIR/src/x.rs`,
+// where the alias is one of the keys of `externals`
+pub fn external_of_reference<V>(reference: &PathBuf, externals: &HashMap<String, V>) -> Option<(String, PathBuf)> {
+    let reference = reference.to_str()?;
+    if reference.starts_with(ID_REFERENCE_PREFIX) || reference.starts_with(NAME_REFERENCE_PREFIX) {
+        return None;
+    }
+    let regex = Regex::new(EXTERNAL_REFERENCE_PATTERN).unwrap();
+    let capture = regex.captures(reference)?;
+    let alias = capture.at(1).unwrap().to_string();
+    if !externals.contains_key(&alias) {
+        return None;
+    }
+    Some((alias, PathBuf::from(capture.at(2).unwrap())))
+}
+
+// names and globs of paths, such as `$ROOT_DIR/src/parser/**`, may refer to any number of entries
+pub fn is_pattern_reference(reference: &PathBuf) -> bool {
+    name_of_reference(reference).is_some()
+        || reference.to_str().map(|reference| reference.contains(&GLOB_CHARS[..])).unwrap_or(false)
+}
+
+// `reference` with the root directory replaced by `$ROOT_DIR`
+pub fn reference_with_root_dir(reference: &PathBuf) -> PathBuf {
+    let root_dir = PathBuf::from(env::var(ROOT_DIR_VAR).unwrap());
+    match reference.strip_prefix(&root_dir) {
+        Ok(relative_path) => PathBuf::from(ROOT_DIR).join(relative_path),
+        Err(_) => reference.clone(),
+    }
+}
+
+// targets of links like `[[$ROOT_DIR/src/parser]]` or `[[id:parser]]` in `description`
+pub fn description_links(description: &str) -> Vec<String> {
+    let regex = Regex::new(LINK_PATTERN).unwrap();
+    regex.captures_iter(description)
+        .map(|capture| capture.at(1).unwrap().trim().to_string())
+        .collect()
+}
+
+// `description` with each link replaced by `replace(target)`
+pub fn replace_links<F>(description: &str, mut replace: F) -> String
+    where   F: FnMut(&str) -> String
+{
+    let regex = Regex::new(LINK_PATTERN).unwrap();
+    let mut replaced = String::new();
+    let mut last_end = 0;
+    for capture in regex.captures_iter(description) {
+        let (start, end) = capture.pos(0).unwrap();
+        replaced.push_str(&description[last_end..start]);
+        replaced.push_str(&replace(capture.at(1).unwrap().trim()));
+        last_end = end;
+    }
+    replaced.push_str(&description[last_end..]);
+    replaced
+}
+
+// items of a value of list type, one on each line
+pub fn list_items(value: &str) -> Vec<String> {
+    value.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect()
+}
+
+// `<property>=<value>` matches entries whose property is the value, or has it as an item if the property is a list.
+// A bare `<value>` matches entries having it as an item of any list property
+#[derive(Clone)]
+pub struct TagFilter {
+    pub property: Option<String>,
+    pub value: String,
+}
+
+impl TagFilter {
+    pub fn from(raw: &str) -> TagFilter {
+        match raw.find('=') {
+            Some(index) => TagFilter {
+                property: Some(raw[..index].trim().to_string()),
+                value: raw[index + 1..].trim().to_string(),
+            },
+            None => TagFilter {
+                property: None,
+                value: raw.trim().to_string(),
+            },
+        }
+    }
+
+    // `value_of` gives the value of a property of the entry to be matched
+    pub fn matches<F>(&self, value_of: F, list_properties: &Vec<String>) -> bool
+        where F: Fn(&str) -> Option<String> {
+        let has_item = |property: &str| {
+            value_of(property).map(|value| list_items(&value).contains(&self.value)).unwrap_or(false)
+        };
+        match &self.property {
+            Some(property) => {
+                if list_properties.contains(property) {
+                    has_item(property)
+                } else {
+                    value_of(property).map(|value| value.trim() == self.value).unwrap_or(false)
+                }
+            },
+            None => {
+                list_properties.iter().any(|property| has_item(property))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(raw: &str, values: &[(&str, &str)], list_properties: &[&str]) -> bool {
+        let values: HashMap<String, String> = values.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect();
+        let list_properties = list_properties.iter().map(|name| name.to_string()).collect();
+        TagFilter::from(raw).matches(|property| values.get(property).cloned(), &list_properties)
+    }
+
+    #[test]
+    fn matches_property_value() {
+        assert!(matches("Owner=infra", &[("Owner", " infra ")], &[]));
+        assert!(!matches("Owner=infra", &[("Owner", "core")], &[]));
+        assert!(!matches("Owner=infra", &[], &[]));
+    }
+
+    #[test]
+    fn matches_list_items() {
+        assert!(matches("Tags=api", &[("Tags", "net\napi")], &["Tags"]));
+        assert!(!matches("Tags=ap", &[("Tags", "net\napi")], &["Tags"]));
+        assert!(matches("api", &[("Tags", "net\napi")], &["Tags"]));
+        assert!(!matches("api", &[("Owner", "api")], &["Tags"]));
+    }
+}
@@ -3,11 +3,13 @@ use std::collections::HashMap;
 
 use crate::strings::*;
 
-#[derive(Copy, Clone)]
+#[derive(Clone, PartialEq)]
 pub enum WisphaEntryType {
     Directory, // if the entry is a directory
     File, // if the entry is a file in a directory
     ProgramEntry, // if the entry is a programmatic stuff in a file
+    Virtual, // if the entry groups other entries, and is not backed by the file system
+    Custom(String), // declared in `entry_types` of config
 }
 
 #[derive(Clone)]
@@ -15,17 +17,28 @@ pub struct WisphaEntryProperties {
     pub entry_type: WisphaEntryType,
     pub name: String,
     pub description: Option<String>, // the whitespace is not allowed at the begin and end
+    pub fingerprint: Option<String>, // hash of the content when the description was last confirmed
+    pub id: Option<String>, // unique in the project, and unchanged when the entry is moved
+    pub kind: Option<String>, // the kind of item of a program entry, such as `struct` or `function`
+    pub line_range: Option<(usize, usize)>, // the first and last lines of a program entry in its file, starting at 1
+    pub symbol: Option<String>, // the identifier of a program entry in code, if it differs from the name
     pub absolute_path: PathBuf, // is absolute in memory, and starts with `$ROOT_DIR` when saved, can also be absolute or relative
     pub file_path: PathBuf, // the absolute path of the file where the entry is directly saved, i.e. not intermediate. Not saved in file
     pub customized: HashMap<String, String>,
+    pub member_path_bufs: Vec<PathBuf>, // entries grouped by a virtual entry
+    pub property_lines: HashMap<String, (usize, usize)>, // line numbers in `file_path` from the header of each property to its last non-blank body line. Not saved in file
+    pub repeated_properties: Vec<String>, // properties not of list type whose headers are repeated. Not saved in file
+    pub dependency_lines: HashMap<PathBuf, usize>, // line number in `file_path` of the header of each dependency, keyed by its reference. Not saved in file
 }
 
 impl WisphaEntryType {
-    pub fn to_str(&self) -> &'static str {
+    pub fn to_str(&self) -> &str {
         match &self {
             WisphaEntryType::Directory => DIRECTORY_TYPE,
             WisphaEntryType::File => FILE_TYPE,
             WisphaEntryType::ProgramEntry => PROGRAM_ENTRY_TYPE,
+            WisphaEntryType::Virtual => VIRTUAL_TYPE,
+            WisphaEntryType::Custom(name) => name.as_str(),
         }
     }
 
@@ -34,7 +47,63 @@ impl WisphaEntryType {
             DIRECTORY_TYPE => Some(WisphaEntryType::Directory),
             FILE_TYPE => Some(WisphaEntryType::File),
             PROGRAM_ENTRY_TYPE => Some(WisphaEntryType::ProgramEntry),
+            VIRTUAL_TYPE => Some(WisphaEntryType::Virtual),
             _ => None,
         }
     }
-}
\ No newline at end of file
+
+    // built-in types, or one of `custom_types`
+    pub fn from_with_custom_types(string: String, custom_types: &Vec<String>) -> Option<WisphaEntryType> {
+        if let Some(entry_type) = WisphaEntryType::from(string.clone()) {
+            return Some(entry_type);
+        }
+        if custom_types.contains(&string) {
+            Some(WisphaEntryType::Custom(string))
+        } else {
+            None
+        }
+    }
+}
+impl WisphaEntryProperties {
+    pub fn is_virtual(&self) -> bool {
+        self.entry_type == WisphaEntryType::Virtual
+    }
+
+    // program entries share the path of their files, and virtual entries have no path
+    pub fn has_own_path(&self) -> bool {
+        match self.entry_type {
+            WisphaEntryType::ProgramEntry | WisphaEntryType::Virtual => false,
+            _ => true,
+        }
+    }
+}
+
+// `12-30` to `(12, 30)`
+pub fn parse_line_range(raw: &str) -> Option<(usize, usize)> {
+    let mut bounds = raw.trim().splitn(2, '-');
+    let first = bounds.next()?.trim().parse().ok()?;
+    let last = bounds.next()?.trim().parse().ok()?;
+    if first == 0 || first > last {
+        return None;
+    }
+    Some((first, last))
+}
+
+pub fn line_range_string(line_range: &(usize, usize)) -> String {
+    format!("{}-{}", line_range.0, line_range.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_line_range() {
+        assert_eq!(parse_line_range("12-30"), Some((12, 30)));
+        assert_eq!(parse_line_range(" 7 - 7 "), Some((7, 7)));
+        assert_eq!(parse_line_range("30-12"), None);
+        assert_eq!(parse_line_range("0-3"), None);
+        assert_eq!(parse_line_range("12"), None);
+        assert_eq!(parse_line_range("a-b"), None);
+    }
+}
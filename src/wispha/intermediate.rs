@@ -39,9 +39,18 @@ impl WisphaDirectEntry {
             entry_type: DEFAULT_ENTRY_TYPE,
             name: String::from(DEFAULT_NAME),
             description: None,
+            fingerprint: None,
+            id: None,
+            kind: None,
+            line_range: None,
+            symbol: None,
             absolute_path: PathBuf::from(DEFAULT_PATH),
             file_path: PathBuf::from(DEFAULT_FILE_PATH),
             customized: HashMap::new(),
+            member_path_bufs: vec![],
+            property_lines: HashMap::new(),
+            repeated_properties: vec![],
+            dependency_lines: HashMap::new(),
         };
 
         let sup_entry = Mutex::new(Weak::new());
@@ -107,8 +116,9 @@ impl WisphaIntermediateEntry {
         match &self {
             Direct(direct_entry) => {
                 let common = Rc::new(RefCell::new(WisphaEntry::default()));
-                callback(Rc::clone(&common));
                 common.borrow_mut().properties = direct_entry.properties.clone();
+                *common.borrow_mut().dependency_path_bufs.borrow_mut() = direct_entry.dependency_path_bufs.lock().unwrap().clone();
+                callback(Rc::clone(&common));
                 let locked_sub_entries = direct_entry.sub_entries.lock().unwrap();
                 for sub_entry in &*locked_sub_entries {
                     let locked_sub_entry = sub_entry.lock().unwrap();
@@ -137,6 +147,7 @@ pub fn take_properties(entry: Arc<Mutex<WisphaIntermediateEntry>>, victim: Arc<M
         let mut locked_victim = victim.lock().unwrap();
         if let Some(direct_victim) = locked_victim.get_direct_entry_mut() {
             direct_entry.properties = direct_victim.properties.clone();
+            direct_entry.dependency_path_bufs = Mutex::new(direct_victim.dependency_path_bufs.lock().unwrap().clone());
 //            let mut locked_entry_sub_entries = direct_entry.sub_entries.lock().unwrap();
 //            let locked_victim_sub_entries = direct_victim.sub_entries.lock().unwrap();
 //            for sub_entry in &*locked_victim_sub_entries {